@@ -0,0 +1,10 @@
+-- Add migration script here
+CREATE TABLE command_owners (
+    guild_id BIGINT NOT NULL,
+
+    -- the user or role that may use admin commands
+    id BIGINT NOT NULL,
+    is_role BOOLEAN NOT NULL,
+
+    PRIMARY KEY(guild_id, id)
+);
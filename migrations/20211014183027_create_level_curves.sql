@@ -0,0 +1,12 @@
+-- Add migration script here
+CREATE TABLE level_curves (
+    guild_id BIGINT PRIMARY KEY,
+
+    -- one of "linear", "quadratic", "mee6" or "custom"
+    kind TEXT NOT NULL,
+    -- the KR per level for linear curves, or the multiplier for quadratic
+    -- curves
+    factor INTEGER NOT NULL DEFAULT 0,
+    -- the KR needed for each level for custom curves
+    thresholds INTEGER[] NOT NULL DEFAULT '{}'
+);
@@ -0,0 +1,11 @@
+-- Add migration script here
+CREATE TABLE level_announcements (
+    guild_id BIGINT PRIMARY KEY,
+
+    -- one of "same", "channel", "dm" or "none"
+    target TEXT NOT NULL DEFAULT 'same',
+    -- the channel to post in, if target is "channel"
+    channel_id BIGINT,
+    -- the message to post, or NULL for the default
+    template TEXT
+);
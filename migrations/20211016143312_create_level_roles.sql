@@ -0,0 +1,17 @@
+-- Add migration script here
+CREATE TABLE level_roles (
+    guild_id BIGINT NOT NULL,
+
+    -- the level needed to get the role
+    level INTEGER NOT NULL,
+    role_id BIGINT NOT NULL,
+
+    PRIMARY KEY(guild_id, role_id)
+);
+
+CREATE TABLE level_role_modes (
+    guild_id BIGINT PRIMARY KEY,
+
+    -- whether lower level roles are removed when a higher one is given
+    replace BOOLEAN NOT NULL DEFAULT FALSE
+);
@@ -0,0 +1,5 @@
+-- Add migration script here
+ALTER TABLE xp
+    -- when the user was last awarded experience for a message, so cooldowns
+    -- survive restarts
+    ADD COLUMN last_awarded_at TIMESTAMPTZ;
@@ -0,0 +1,13 @@
+-- Add migration script here
+CREATE TABLE xp_multipliers (
+    guild_id BIGINT NOT NULL,
+
+    -- the channel, category or role the multiplier applies to
+    target_id BIGINT NOT NULL,
+    -- one of "channel", "category" or "role"
+    kind TEXT NOT NULL,
+    -- 0 means no experience is awarded
+    multiplier DOUBLE PRECISION NOT NULL,
+
+    PRIMARY KEY(guild_id, target_id)
+);
@@ -0,0 +1,19 @@
+-- Add migration script here
+CREATE TABLE xp_audit_log (
+    id BIGSERIAL PRIMARY KEY,
+    guild_id BIGINT NOT NULL,
+
+    -- who made the change
+    moderator_id BIGINT NOT NULL,
+    -- whose experience was changed, or NULL if it was the whole guild
+    user_id BIGINT,
+
+    -- one of "give", "take", "set", "reset user" or "reset guild"
+    action TEXT NOT NULL,
+    amount INTEGER,
+    reason TEXT,
+
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+CREATE INDEX xp_audit_log_guild_id ON xp_audit_log (guild_id);
@@ -0,0 +1,15 @@
+-- Add migration script here
+CREATE TABLE xp_ledger (
+    id BIGSERIAL PRIMARY KEY,
+    guild_id BIGINT NOT NULL,
+    user_id BIGINT NOT NULL,
+
+    -- how much experience was gained, negative if it was lost
+    amount INTEGER NOT NULL,
+    -- one of "message" or "admin"
+    source TEXT NOT NULL,
+
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+CREATE INDEX xp_ledger_guild_id_created_at ON xp_ledger (guild_id, created_at);
@@ -0,0 +1,16 @@
+-- Add migration script here
+CREATE TABLE xp_decay (
+    guild_id BIGINT PRIMARY KEY,
+
+    -- how much experience inactive users lose every week, out of 100
+    percent INTEGER NOT NULL,
+    -- how many days a user has to be inactive before they start losing
+    -- experience
+    after_days INTEGER NOT NULL,
+    -- users never decay below this much experience
+    floor INTEGER NOT NULL DEFAULT 0
+);
+
+ALTER TABLE xp
+    -- when the user last lost experience to decay
+    ADD COLUMN last_decayed_at TIMESTAMPTZ;
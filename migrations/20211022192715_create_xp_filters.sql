@@ -0,0 +1,14 @@
+-- Add migration script here
+CREATE TABLE xp_filters (
+    guild_id BIGINT PRIMARY KEY,
+
+    -- messages shorter than this many characters don't give experience
+    min_length INTEGER NOT NULL DEFAULT 0,
+    -- whether messages similar to the user's recent messages are ignored
+    duplicates BOOLEAN NOT NULL DEFAULT false,
+    -- whether messages that are only emoji or attachments are ignored
+    emoji_only BOOLEAN NOT NULL DEFAULT false,
+    -- the most experience a user can get from messages in an hour, or NULL
+    -- for no limit
+    hourly_cap INTEGER
+);
@@ -0,0 +1,49 @@
+-- Add migration script here
+
+-- emojis used to be stored as a BIGINT. unicode emojis kept only their first
+-- codepoint, which is converted back to the emoji. custom emojis could have
+-- their ids mixed up, so they can't be converted; they are kept with a
+-- "legacy:" prefix and replaced with the real emoji the next time it is
+-- reacted with.
+--
+-- some codepoints are only the start of an emoji, and discord never sends
+-- them alone: regional indicators (flags), keycap bases and the emojis that
+-- are shown as text unless they are followed by U+FE0F (❤️). these are kept
+-- as "legacy:" too, and are replaced the same way.
+ALTER TABLE reaction_roles
+    ALTER COLUMN emoji TYPE TEXT USING CASE
+        -- regional indicators
+        WHEN emoji BETWEEN 127462 AND 127487 THEN 'legacy:' || emoji::TEXT
+        -- keycap bases: '#', '*' and the digits
+        WHEN emoji IN (35, 42) OR emoji BETWEEN 48 AND 57 THEN 'legacy:' || emoji::TEXT
+        -- emojis with text presentation by default
+        WHEN emoji = ANY(ARRAY[
+                169, 174, 8252, 8265, 8482, 8505, 8596, 8597, 8598, 8599, 8600,
+                8601, 8617, 8618, 9000, 9167, 9197, 9198, 9199, 9201, 9202, 9208,
+                9209, 9210, 9410, 9642, 9643, 9654, 9664, 9723, 9724, 9728, 9729,
+                9730, 9731, 9732, 9742, 9745, 9752, 9757, 9760, 9762, 9763, 9766,
+                9770, 9774, 9775, 9784, 9785, 9786, 9792, 9794, 9823, 9824, 9827,
+                9829, 9830, 9832, 9851, 9854, 9874, 9876, 9877, 9878, 9879, 9881,
+                9883, 9884, 9888, 9895, 9904, 9905, 9928, 9935, 9937, 9939, 9961,
+                9968, 9969, 9972, 9975, 9976, 9977, 9986, 9992, 9993, 9996, 9997,
+                9999, 10002, 10004, 10006, 10013, 10017, 10035, 10036, 10052,
+                10055, 10083, 10084, 10145, 10548, 10549, 11013, 11014, 11015,
+                12336, 12349, 12951, 12953, 127344, 127345, 127358, 127359, 127490,
+                127543, 127777, 127780, 127781, 127782, 127783, 127784, 127785,
+                127786, 127787, 127788, 127798, 127869, 127894, 127895, 127897,
+                127898, 127899, 127902, 127903, 127947, 127948, 127949, 127950,
+                127956, 127957, 127958, 127959, 127960, 127961, 127962, 127963,
+                127964, 127965, 127966, 127967, 127987, 127989, 127991, 128063,
+                128065, 128253, 128329, 128330, 128367, 128368, 128371, 128372,
+                128373, 128374, 128375, 128376, 128377, 128391, 128394, 128395,
+                128396, 128397, 128400, 128421, 128424, 128433, 128434, 128444,
+                128450, 128451, 128452, 128465, 128466, 128467, 128476, 128477,
+                128478, 128481, 128483, 128488, 128495, 128499, 128506, 128715,
+                128717, 128718, 128719, 128736, 128737, 128738, 128739, 128740,
+                128741, 128745, 128752, 128755
+            ]) THEN 'legacy:' || emoji::TEXT
+        -- every codepoint but NUL and surrogates is a valid character
+        WHEN emoji > 0 AND emoji < 1114112 AND emoji NOT BETWEEN 55296 AND 57343
+            THEN chr(emoji::INTEGER)
+        ELSE 'legacy:' || emoji::TEXT
+    END;
@@ -0,0 +1,4 @@
+-- Add migration script here
+ALTER TABLE reaction_roles
+    -- one of "normal", "unique", "verify", "drop" or "toggle"
+    ADD COLUMN mode TEXT NOT NULL DEFAULT 'normal';
@@ -0,0 +1,29 @@
+-- Add migration script here
+CREATE TABLE role_menus (
+    id BIGSERIAL PRIMARY KEY,
+    guild_id BIGINT NOT NULL,
+
+    -- the message the bot posted
+    channel_id BIGINT NOT NULL,
+    message_id BIGINT NOT NULL UNIQUE,
+
+    -- one of "buttons" or "select"
+    kind TEXT NOT NULL,
+    content TEXT NOT NULL
+);
+
+CREATE INDEX role_menus_guild_id ON role_menus (guild_id);
+
+CREATE TABLE role_menu_roles (
+    menu_id BIGINT NOT NULL REFERENCES role_menus (id) ON DELETE CASCADE,
+    role_id BIGINT NOT NULL,
+
+    label TEXT NOT NULL,
+    -- stored like reaction_roles.emoji
+    emoji TEXT,
+
+    -- roles are shown in the order they were added
+    added_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+
+    PRIMARY KEY (menu_id, role_id)
+);
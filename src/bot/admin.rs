@@ -0,0 +1,337 @@
+//! Admin commands for managing experience.
+
+use crate::bot::xp::Xp;
+use crate::command::chat::Arguments;
+use crate::command::component::{button, custom_id, Arguments as ComponentArguments};
+use crate::command::spec::{CommandSpec, Options};
+use crate::model::audit::{self, Action};
+use crate::model::xp::{Guild, Source};
+use crate::service::{Context, Error};
+use crate::{impl_command, impl_component, options, state};
+
+use twilight_model::application::component::{button::ButtonStyle, Component as TwilightComponent};
+use twilight_model::id::{GuildId, UserId};
+
+use twilight_mention::Mention;
+
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use dashmap::DashMap;
+
+use anyhow::anyhow;
+
+/// The definition of `/xp`.
+pub fn spec(xp: &Xp, resets: &PendingResets) -> CommandSpec {
+    CommandSpec::new("xp", "manage the KR of members")
+        .default_permission(false)
+        .subcommand::<AmountOptions>("give", "gives KR to a member", ChangeXp(ChangeKind::Give))
+        .subcommand::<AmountOptions>("take", "takes KR from a member", ChangeXp(ChangeKind::Take))
+        .subcommand::<AmountOptions>("set", "sets the KR of a member", ChangeXp(ChangeKind::Set))
+        .group(
+            CommandSpec::new("reset", "reset KR")
+                .subcommand::<ResetUserOptions>(
+                    "user",
+                    "resets the KR of a member",
+                    ResetUser(xp.clone()),
+                )
+                .subcommand::<ReasonOptions>(
+                    "guild",
+                    "resets the KR of everyone in the server",
+                    ResetGuild(resets.clone()),
+                ),
+        )
+}
+
+options! {
+    /// Options for `/xp give`, `/xp take` and `/xp set`.
+    pub struct AmountOptions {
+        /// the member to change
+        user: UserId,
+        /// how much KR
+        amount: i64,
+        /// why you're making the change
+        reason: Option<String>,
+    }
+}
+
+options! {
+    /// Options for `/xp reset user`.
+    pub struct ResetUserOptions {
+        /// the member to reset
+        user: UserId,
+        /// why you're making the change
+        reason: Option<String>,
+    }
+}
+
+options! {
+    /// Options for `/xp reset guild`.
+    pub struct ReasonOptions {
+        /// why you're making the change
+        reason: Option<String>,
+    }
+}
+
+/// A kind of change that can be made with `/xp`.
+#[derive(Clone, Copy, Debug)]
+pub enum ChangeKind {
+    Give,
+    Take,
+    Set,
+}
+
+/// The `/xp give`, `/xp take` and `/xp set` commands.
+#[derive(Clone)]
+pub struct ChangeXp(pub ChangeKind);
+
+impl_command! {
+    impl Command for ChangeXp {
+        async fn command(&self, cx: &Context, command: Arguments<'_>) -> Result<(), Error> {
+            let guild_id = command.guild_id().ok_or(anyhow!("guild_id is missing"))?;
+
+            let options = AmountOptions::parse(&command)?;
+
+            let amount = match i32::try_from(options.amount) {
+                Ok(amount) if amount >= 0 => amount,
+                _ => {
+                    return command
+                        .respond()
+                        .content("that isn't a valid amount of KR!")
+                        .ephemeral()
+                        .exec(cx.http())
+                        .await;
+                }
+            };
+
+            let guild = Guild::new(guild_id);
+            let mut tx = cx.db().begin().await?;
+
+            let action = match self.0 {
+                ChangeKind::Give => {
+                    guild.add(&mut tx, options.user, amount, Source::Admin).await?;
+                    Action::Give(amount)
+                }
+                ChangeKind::Take => {
+                    // don't take more than they have
+                    let score = guild.get(&mut tx, options.user).await?.score();
+                    let amount = amount.min(score.max(0));
+
+                    guild.add(&mut tx, options.user, -amount, Source::Admin).await?;
+                    Action::Take(amount)
+                }
+                ChangeKind::Set => {
+                    guild.set(&mut tx, options.user, amount, Source::Admin).await?;
+                    Action::Set(amount)
+                }
+            };
+
+            audit::Guild::new(guild_id)
+                .log(
+                    &mut tx,
+                    command.user_id(),
+                    Some(options.user),
+                    action,
+                    options.reason.as_deref(),
+                )
+                .await?;
+
+            let score = guild.get(&mut tx, options.user).await?.score();
+
+            tx.commit().await?;
+
+            command
+                .respond()
+                .content(format!("{} now has {}KR!", options.user.mention(), score))
+                .ephemeral()
+                .exec(cx.http())
+                .await
+        }
+    }
+}
+
+/// The `/xp reset user` command.
+#[derive(Clone)]
+pub struct ResetUser(pub Xp);
+
+impl_command! {
+    impl Command for ResetUser {
+        async fn command(&self, cx: &Context, command: Arguments<'_>) -> Result<(), Error> {
+            let guild_id = command.guild_id().ok_or(anyhow!("guild_id is missing"))?;
+
+            let options = ResetUserOptions::parse(&command)?;
+
+            // otherwise, the next flush would give some of it back
+            self.0.forget_user(guild_id, options.user);
+
+            let mut tx = cx.db().begin().await?;
+
+            let content = if Guild::new(guild_id)
+                .remove(&mut tx, options.user, Source::Admin)
+                .await? {
+                audit::Guild::new(guild_id)
+                    .log(
+                        &mut tx,
+                        command.user_id(),
+                        Some(options.user),
+                        Action::ResetUser,
+                        options.reason.as_deref(),
+                    )
+                    .await?;
+
+                format!("reset the KR of {}!", options.user.mention())
+            } else {
+                format!("{} doesn't have any KR!", options.user.mention())
+            };
+
+            tx.commit().await?;
+
+            command
+                .respond()
+                .content(content)
+                .ephemeral()
+                .exec(cx.http())
+                .await
+        }
+    }
+}
+
+/// Guild resets waiting to be confirmed.
+///
+/// The reason for a reset may be too long for a `custom_id`, so it is kept
+/// here instead. This type is cheap to clone.
+#[derive(Clone, Debug, Default)]
+pub struct PendingResets(Arc<DashMap<GuildId, PendingReset>>);
+
+#[derive(Debug)]
+struct PendingReset {
+    moderator_id: UserId,
+    reason: Option<String>,
+    requested_at: Instant,
+}
+
+impl PendingResets {
+    /// How long a reset can be confirmed for.
+    pub const TIMEOUT: Duration = Duration::from_secs(60);
+
+    fn insert(&self, guild_id: GuildId, moderator_id: UserId, reason: Option<String>) {
+        let reset = PendingReset {
+            moderator_id,
+            reason,
+            requested_at: Instant::now(),
+        };
+
+        self.0.insert(guild_id, reset);
+    }
+
+    fn take(&self, guild_id: GuildId, moderator_id: UserId) -> Option<PendingReset> {
+        self.0
+            .remove_if(&guild_id, |_, reset| {
+                reset.moderator_id == moderator_id
+                    && reset.requested_at.elapsed() < PendingResets::TIMEOUT
+            })
+            .map(|(_, reset)| reset)
+    }
+}
+
+state! {
+    /// The state of the `/xp reset guild` confirmation buttons.
+    pub struct ResetGuildState {
+        /// Whether the reset was confirmed.
+        confirm: bool,
+    }
+}
+
+/// The `/xp reset guild` command.
+#[derive(Clone)]
+pub struct ResetGuild(pub PendingResets);
+
+impl_command! {
+    impl Command for ResetGuild {
+        async fn command(&self, cx: &Context, command: Arguments<'_>) -> Result<(), Error> {
+            let guild_id = command.guild_id().ok_or(anyhow!("guild_id is missing"))?;
+
+            let reason = ReasonOptions::parse(&command)?.reason;
+
+            self.0.insert(guild_id, command.user_id(), reason);
+
+            let buttons = [
+                button(
+                    ButtonStyle::Danger,
+                    custom_id("xpreset", &ResetGuildState { confirm: true }),
+                    "Reset everyone",
+                ),
+                button(
+                    ButtonStyle::Secondary,
+                    custom_id("xpreset", &ResetGuildState { confirm: false }),
+                    "Cancel",
+                ),
+            ];
+
+            command
+                .respond()
+                .content(
+                    "⚠️ this will reset the KR of **everyone** in the server, and \
+                     can't be undone! are you sure?",
+                )
+                .action_row(buttons.into_iter().map(TwilightComponent::Button))
+                .ephemeral()
+                .exec(cx.http())
+                .await
+        }
+    }
+}
+
+/// Handles the `/xp reset guild` confirmation buttons.
+#[derive(Clone)]
+pub struct ResetGuildButtons(pub PendingResets, pub Xp);
+
+impl_component! {
+    impl Component for ResetGuildButtons {
+        async fn component(
+            &self,
+            cx: &Context,
+            component: ComponentArguments<'_>,
+        ) -> Result<(), Error> {
+            let guild_id = component.guild_id().ok_or(anyhow!("guild_id is missing"))?;
+
+            let confirm = component.state::<ResetGuildState>()?.confirm;
+
+            let content = match self.0.take(guild_id, component.user_id()) {
+                Some(reset) if confirm => {
+                    // otherwise, the next flush would give some of it back
+                    self.1.forget_guild(guild_id);
+
+                    let mut tx = cx.db().begin().await?;
+
+                    let removed = Guild::new(guild_id).clear(&mut tx, Source::Admin).await?;
+
+                    audit::Guild::new(guild_id)
+                        .log(
+                            &mut tx,
+                            reset.moderator_id,
+                            None,
+                            Action::ResetGuild,
+                            reset.reason.as_deref(),
+                        )
+                        .await?;
+
+                    tx.commit().await?;
+
+                    format!("reset the KR of {} members!", removed)
+                }
+                Some(_) => String::from("nothing was reset!"),
+                None => String::from(
+                    "this has expired! try `/xp reset guild` again to continue",
+                ),
+            };
+
+            component
+                .update()
+                .content(content)
+                .clear_components()
+                .exec(cx.http())
+                .await
+        }
+    }
+}
@@ -0,0 +1,118 @@
+//! Commands that change how level ups are announced.
+
+use crate::command::chat::Arguments;
+use crate::command::spec::Options;
+use crate::model::announcement::{self, Target};
+use crate::service::{Context, Error};
+use crate::{impl_command, options};
+
+use twilight_model::id::ChannelId;
+
+use anyhow::anyhow;
+
+options! {
+    /// Options for `/config announcements channel`.
+    pub struct ChannelOptions {
+        /// the channel to announce level ups in
+        channel: ChannelId,
+    }
+}
+
+options! {
+    /// Options for `/config announcements message`.
+    pub struct TemplateOptions {
+        /// the message, with {user}, {level} and {kr}. leave empty to reset
+        template: Option<String>,
+    }
+}
+
+/// A kind of [`Target`] that can be picked with `/config announcements`.
+#[derive(Clone, Copy, Debug)]
+pub enum TargetKind {
+    Same,
+    Channel,
+    Dm,
+    Off,
+}
+
+/// The `/config announcements` commands that change where level ups are
+/// announced.
+#[derive(Clone)]
+pub struct SetAnnouncementTarget(pub TargetKind);
+
+impl_command! {
+    impl Command for SetAnnouncementTarget {
+        async fn command(&self, cx: &Context, command: Arguments<'_>) -> Result<(), Error> {
+            let guild_id = command.guild_id().ok_or(anyhow!("guild_id is missing"))?;
+
+            let (target, content) = match self.0 {
+                TargetKind::Same => (
+                    Target::Same,
+                    String::from("level ups will be announced where they happen!"),
+                ),
+                TargetKind::Channel => {
+                    let channel_id = ChannelOptions::parse(&command)?.channel;
+
+                    (
+                        Target::Channel(channel_id),
+                        format!("level ups will be announced in <#{}>!", channel_id),
+                    )
+                }
+                TargetKind::Dm => (
+                    Target::Dm,
+                    String::from("level ups will be announced in DMs!"),
+                ),
+                TargetKind::Off => (
+                    Target::None,
+                    String::from("level ups won't be announced anymore!"),
+                ),
+            };
+
+            announcement::Guild::new(guild_id)
+                .set_target(cx.db(), target)
+                .await?;
+
+            command
+                .respond()
+                .content(content)
+                .ephemeral()
+                .exec(cx.http())
+                .await
+        }
+    }
+}
+
+/// The `/config announcements message` command.
+#[derive(Default, Clone)]
+pub struct SetAnnouncementTemplate;
+
+impl_command! {
+    impl Command for SetAnnouncementTemplate {
+        async fn command(&self, cx: &Context, command: Arguments<'_>) -> Result<(), Error> {
+            let guild_id = command.guild_id().ok_or(anyhow!("guild_id is missing"))?;
+
+            let template = TemplateOptions::parse(&command)?.template;
+
+            announcement::Guild::new(guild_id)
+                .set_template(cx.db(), template.as_deref())
+                .await?;
+
+            // show the user what it looks like
+            let preview = announcement::render(
+                template
+                    .as_deref()
+                    .unwrap_or(announcement::Announcement::DEFAULT_TEMPLATE),
+                command.user_id(),
+                5,
+                500,
+            );
+
+            command
+                .respond()
+                .content(format!("level ups will look like this:\n{}", preview))
+                .ephemeral()
+                .exec(cx.http())
+                .await
+        }
+    }
+}
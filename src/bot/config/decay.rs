@@ -0,0 +1,113 @@
+//! Commands that make inactive members lose KR.
+
+use crate::command::chat::Arguments;
+use crate::command::spec::Options;
+use crate::model::decay::{self, Policy};
+use crate::service::{Context, Error};
+use crate::{impl_command, options};
+
+use anyhow::anyhow;
+
+options! {
+    /// Options for `/config decay set`.
+    pub struct DecayOptions {
+        /// how much of their KR members lose every week, in percent
+        percent: i64,
+        /// how many days members have to be inactive before losing KR
+        after: i64,
+        /// the KR members never go below
+        floor: Option<i64>,
+    }
+}
+
+/// The `/config decay set` command.
+#[derive(Default, Clone)]
+pub struct SetDecay;
+
+impl_command! {
+    impl Command for SetDecay {
+        async fn command(&self, cx: &Context, command: Arguments<'_>) -> Result<(), Error> {
+            let guild_id = command.guild_id().ok_or(anyhow!("guild_id is missing"))?;
+
+            let options = DecayOptions::parse(&command)?;
+
+            let percent = i32::try_from(options.percent).ok().filter(|p| (1..=100).contains(p));
+            let after_days = i32::try_from(options.after).ok().filter(|d| *d > 0);
+            let floor = i32::try_from(options.floor.unwrap_or(0)).ok().filter(|f| *f >= 0);
+
+            let content = match (percent, after_days, floor) {
+                (None, _, _) => String::from("the percent has to be between 1 and 100!"),
+                (_, None, _) => String::from("members have to be inactive for at least a day!"),
+                (_, _, None) => String::from("that isn't a valid amount of KR!"),
+                (Some(percent), Some(after_days), Some(floor)) => {
+                    let policy = Policy::new(percent, after_days, floor);
+
+                    decay::Guild::new(guild_id)
+                        .set_policy(cx.db(), policy)
+                        .await?;
+
+                    format!("{}!", policy)
+                }
+            };
+
+            command
+                .respond()
+                .content(content)
+                .ephemeral()
+                .exec(cx.http())
+                .await
+        }
+    }
+}
+
+/// The `/config decay off` command.
+#[derive(Default, Clone)]
+pub struct DisableDecay;
+
+impl_command! {
+    impl Command for DisableDecay {
+        async fn command(&self, cx: &Context, command: Arguments<'_>) -> Result<(), Error> {
+            let guild_id = command.guild_id().ok_or(anyhow!("guild_id is missing"))?;
+
+            let content = if decay::Guild::new(guild_id)
+                .remove_policy(cx.db())
+                .await?
+            {
+                "inactive members won't lose KR anymore!"
+            } else {
+                "inactive members already don't lose KR!"
+            };
+
+            command
+                .respond()
+                .content(content)
+                .ephemeral()
+                .exec(cx.http())
+                .await
+        }
+    }
+}
+
+/// The `/config decay show` command.
+#[derive(Default, Clone)]
+pub struct ShowDecay;
+
+impl_command! {
+    impl Command for ShowDecay {
+        async fn command(&self, cx: &Context, command: Arguments<'_>) -> Result<(), Error> {
+            let guild_id = command.guild_id().ok_or(anyhow!("guild_id is missing"))?;
+
+            let content = match decay::Guild::new(guild_id).policy(cx.db()).await? {
+                Some(policy) => format!("{}.", policy),
+                None => String::from("inactive members don't lose KR!"),
+            };
+
+            command
+                .respond()
+                .content(content)
+                .ephemeral()
+                .exec(cx.http())
+                .await
+        }
+    }
+}
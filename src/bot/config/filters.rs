@@ -0,0 +1,226 @@
+//! Commands that stop spam from giving KR.
+
+use crate::bot::xp::SpamFilters;
+use crate::command::chat::Arguments;
+use crate::command::spec::Options;
+use crate::model::filter;
+use crate::service::{Context, Error};
+use crate::{impl_command, options};
+
+use std::fmt::Write;
+
+use anyhow::anyhow;
+
+options! {
+    /// Options for `/config filters length`.
+    pub struct LengthOptions {
+        /// the fewest characters a message needs, or 0 to allow any length
+        length: i64,
+    }
+}
+
+options! {
+    /// Options for `/config filters duplicates` and `/config filters emoji`.
+    pub struct ToggleOptions {
+        /// whether to ignore these messages
+        enabled: bool,
+    }
+}
+
+options! {
+    /// Options for `/config filters cap`.
+    pub struct CapOptions {
+        /// the most KR members can get in an hour; leave empty for no limit
+        kr: Option<i64>,
+    }
+}
+
+/// The `/config filters length` command.
+#[derive(Clone)]
+pub struct SetMinLength(pub SpamFilters);
+
+impl SetMinLength {
+    /// The longest minimum length that can be set.
+    pub const MAX: i32 = 200;
+}
+
+impl_command! {
+    impl Command for SetMinLength {
+        async fn command(&self, cx: &Context, command: Arguments<'_>) -> Result<(), Error> {
+            let guild_id = command.guild_id().ok_or(anyhow!("guild_id is missing"))?;
+
+            let length = LengthOptions::parse(&command)?.length;
+
+            let content = match i32::try_from(length) {
+                Ok(length) if (0..=Self::MAX).contains(&length) => {
+                    filter::Guild::new(guild_id)
+                        .set_min_length(cx.db(), length)
+                        .await?;
+                    self.0.invalidate(guild_id);
+
+                    if length == 0 {
+                        String::from("messages of any length will now give KR!")
+                    } else {
+                        format!(
+                            "messages shorter than {} characters won't give KR anymore!",
+                            length
+                        )
+                    }
+                }
+                _ => format!("the length has to be between 0 and {}!", Self::MAX),
+            };
+
+            command
+                .respond()
+                .content(content)
+                .ephemeral()
+                .exec(cx.http())
+                .await
+        }
+    }
+}
+
+/// A filter that can be turned on or off with `/config filters`.
+#[derive(Clone, Copy, Debug)]
+pub enum FilterKind {
+    Duplicates,
+    EmojiOnly,
+}
+
+/// The `/config filters duplicates` and `/config filters emoji` commands.
+#[derive(Clone)]
+pub struct SetFilter(pub SpamFilters, pub FilterKind);
+
+impl_command! {
+    impl Command for SetFilter {
+        async fn command(&self, cx: &Context, command: Arguments<'_>) -> Result<(), Error> {
+            let guild_id = command.guild_id().ok_or(anyhow!("guild_id is missing"))?;
+
+            let enabled = ToggleOptions::parse(&command)?.enabled;
+
+            let guild = filter::Guild::new(guild_id);
+
+            let content = match self.1 {
+                FilterKind::Duplicates => {
+                    guild.set_duplicates(cx.db(), enabled).await?;
+
+                    if enabled {
+                        "messages similar to a member's recent messages won't give KR anymore!"
+                    } else {
+                        "repeated messages will now give KR!"
+                    }
+                }
+                FilterKind::EmojiOnly => {
+                    guild.set_emoji_only(cx.db(), enabled).await?;
+
+                    if enabled {
+                        "messages that are only emoji or attachments won't give KR anymore!"
+                    } else {
+                        "messages that are only emoji or attachments will now give KR!"
+                    }
+                }
+            };
+
+            self.0.invalidate(guild_id);
+
+            command
+                .respond()
+                .content(content)
+                .ephemeral()
+                .exec(cx.http())
+                .await
+        }
+    }
+}
+
+/// The `/config filters cap` command.
+#[derive(Default, Clone)]
+pub struct SetHourlyCap;
+
+impl_command! {
+    impl Command for SetHourlyCap {
+        async fn command(&self, cx: &Context, command: Arguments<'_>) -> Result<(), Error> {
+            let guild_id = command.guild_id().ok_or(anyhow!("guild_id is missing"))?;
+
+            let kr = CapOptions::parse(&command)?.kr;
+
+            let content = match kr.map(i32::try_from) {
+                None => {
+                    filter::Guild::new(guild_id)
+                        .set_hourly_cap(cx.db(), None)
+                        .await?;
+
+                    String::from("members can now get any amount of KR in an hour!")
+                }
+                Some(Ok(kr)) if kr > 0 => {
+                    filter::Guild::new(guild_id)
+                        .set_hourly_cap(cx.db(), Some(kr))
+                        .await?;
+
+                    format!("members can now get at most {}KR in an hour!", kr)
+                }
+                Some(_) => String::from("the cap has to be more than 0KR!"),
+            };
+
+            command
+                .respond()
+                .content(content)
+                .ephemeral()
+                .exec(cx.http())
+                .await
+        }
+    }
+}
+
+/// The `/config filters show` command.
+#[derive(Default, Clone)]
+pub struct ShowFilters;
+
+impl_command! {
+    impl Command for ShowFilters {
+        async fn command(&self, cx: &Context, command: Arguments<'_>) -> Result<(), Error> {
+            let guild_id = command.guild_id().ok_or(anyhow!("guild_id is missing"))?;
+
+            let filters = filter::Guild::new(guild_id).filters(cx.db()).await?;
+
+            let mut content = String::from("messages don't give KR if they are:");
+            let mut any = false;
+
+            if filters.min_length() > 0 {
+                write!(
+                    content,
+                    "\n• shorter than {} characters",
+                    filters.min_length()
+                )
+                .unwrap();
+                any = true;
+            }
+
+            if filters.duplicates() {
+                content.push_str("\n• similar to the member's recent messages");
+                any = true;
+            }
+
+            if filters.emoji_only() {
+                content.push_str("\n• only emoji or attachments");
+                any = true;
+            }
+
+            if let Some(cap) = filters.hourly_cap() {
+                write!(content, "\n• past the {}KR members can get in an hour", cap).unwrap();
+                any = true;
+            }
+
+            if !any {
+                content = String::from("no messages are filtered!");
+            }
+
+            command
+                .respond()
+                .content(content)
+                .ephemeral()
+                .exec(cx.http())
+                .await
+        }
+    }
+}
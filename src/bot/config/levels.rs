@@ -0,0 +1,130 @@
+//! Commands that change how much KR each level takes.
+
+use crate::command::chat::Arguments;
+use crate::command::spec::Options;
+use crate::model::level::{self, Curve};
+use crate::service::{Context, Error};
+use crate::{impl_command, options};
+
+use std::fmt::Write;
+
+use anyhow::anyhow;
+
+options! {
+    /// Options for `/config levels linear`.
+    pub struct LinearOptions {
+        /// the KR each level takes
+        kr: i64,
+    }
+}
+
+options! {
+    /// Options for `/config levels quadratic`.
+    pub struct QuadraticOptions {
+        /// level n takes this much KR times (n - 1)²
+        factor: i64,
+    }
+}
+
+options! {
+    /// Options for `/config levels custom`.
+    pub struct CustomOptions {
+        /// the KR needed for level 2, 3, 4 and so on, seperated by commas
+        thresholds: String,
+    }
+}
+
+/// A kind of [`Curve`] that can be picked with `/config levels`.
+#[derive(Clone, Copy, Debug)]
+pub enum CurveKind {
+    Linear,
+    Quadratic,
+    Mee6,
+    Custom,
+}
+
+/// The `/config levels` commands that change the level curve.
+#[derive(Clone)]
+pub struct SetLevelCurve(pub CurveKind);
+
+impl_command! {
+    impl Command for SetLevelCurve {
+        async fn command(&self, cx: &Context, command: Arguments<'_>) -> Result<(), Error> {
+            let guild_id = command.guild_id().ok_or(anyhow!("guild_id is missing"))?;
+
+            let curve = match self.0 {
+                CurveKind::Linear => LinearOptions::parse(&command)?
+                    .kr
+                    .try_into()
+                    .ok()
+                    .map(Curve::Linear),
+                CurveKind::Quadratic => QuadraticOptions::parse(&command)?
+                    .factor
+                    .try_into()
+                    .ok()
+                    .map(Curve::Quadratic),
+                CurveKind::Mee6 => Some(Curve::Mee6),
+                CurveKind::Custom => CustomOptions::parse(&command)?
+                    .thresholds
+                    .split(',')
+                    .map(|threshold| threshold.trim().parse::<i32>())
+                    .collect::<Result<Vec<_>, _>>()
+                    .ok()
+                    .map(Curve::Custom),
+            };
+
+            let content = match curve {
+                Some(curve) if curve.is_valid() => {
+                    level::Guild::new(guild_id).set_curve(cx.db(), &curve).await?;
+
+                    format!("levels are now {}!", curve)
+                }
+                _ => String::from(
+                    "that doesn't work! every level needs to take more KR than the last.",
+                ),
+            };
+
+            command
+                .respond()
+                .content(content)
+                .ephemeral()
+                .exec(cx.http())
+                .await
+        }
+    }
+}
+
+/// The `/config levels show` command.
+#[derive(Default, Clone)]
+pub struct ShowLevelCurve;
+
+impl ShowLevelCurve {
+    /// How many levels are listed.
+    pub const LEVELS: i32 = 10;
+}
+
+impl_command! {
+    impl Command for ShowLevelCurve {
+        async fn command(&self, cx: &Context, command: Arguments<'_>) -> Result<(), Error> {
+            let guild_id = command.guild_id().ok_or(anyhow!("guild_id is missing"))?;
+
+            let curve = level::Guild::new(guild_id).curve(cx.db()).await?;
+
+            let mut content = format!("levels are {}", curve);
+
+            for level in 2..=Self::LEVELS + 1 {
+                match curve.score_for(level) {
+                    Some(score) => write!(content, "\n• level {}: {}KR", level, score).unwrap(),
+                    None => break,
+                }
+            }
+
+            command
+                .respond()
+                .content(content)
+                .ephemeral()
+                .exec(cx.http())
+                .await
+        }
+    }
+}
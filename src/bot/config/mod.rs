@@ -0,0 +1,165 @@
+//! Per-guild configuration commands.
+
+pub mod announcements;
+pub mod decay;
+pub mod filters;
+pub mod levels;
+pub mod multipliers;
+pub mod permissions;
+
+use crate::bot::xp::{Multipliers, SpamFilters};
+use crate::command::spec::CommandSpec;
+
+use announcements::{
+    ChannelOptions, SetAnnouncementTarget, SetAnnouncementTemplate, TargetKind, TemplateOptions,
+};
+use decay::{DecayOptions, DisableDecay, SetDecay, ShowDecay};
+use filters::{
+    CapOptions, FilterKind, LengthOptions, SetFilter, SetHourlyCap, SetMinLength, ShowFilters,
+    ToggleOptions,
+};
+use levels::{
+    CurveKind, CustomOptions, LinearOptions, QuadraticOptions, SetLevelCurve, ShowLevelCurve,
+};
+use multipliers::{
+    CategoryMultiplierOptions, ChannelMultiplierOptions, ListMultipliers, MultiplierKind,
+    RemoveMultiplier, RemoveMultiplierOptions, RoleMultiplierOptions, SetMultiplier,
+};
+use permissions::{AddOwner, ListOwners, OwnerOptions, Owners, RemoveOwner};
+
+/// The definition of `/config`.
+///
+/// The handlers share their state with the services that read the
+/// configuration.
+pub fn spec(owners: &Owners, multipliers: &Multipliers, filters: &SpamFilters) -> CommandSpec {
+    CommandSpec::new("config", "configure the bot for this server")
+        .default_permission(false)
+        .group(
+            CommandSpec::new("permissions", "manage who can use admin commands")
+                .subcommand::<OwnerOptions>(
+                    "add",
+                    "allows a user or role to use admin commands",
+                    AddOwner(owners.clone()),
+                )
+                .subcommand::<OwnerOptions>(
+                    "remove",
+                    "disallows a user or role from using admin commands",
+                    RemoveOwner(owners.clone()),
+                )
+                .subcommand::<()>(
+                    "list",
+                    "lists who can use admin commands",
+                    ListOwners(owners.clone()),
+                ),
+        )
+        .group(
+            CommandSpec::new("levels", "change how much KR each level takes")
+                .subcommand::<LinearOptions>(
+                    "linear",
+                    "every level takes the same amount of KR",
+                    SetLevelCurve(CurveKind::Linear),
+                )
+                .subcommand::<QuadraticOptions>(
+                    "quadratic",
+                    "each level takes more KR than the last",
+                    SetLevelCurve(CurveKind::Quadratic),
+                )
+                .subcommand::<()>(
+                    "mee6",
+                    "use the same levels as MEE6",
+                    SetLevelCurve(CurveKind::Mee6),
+                )
+                .subcommand::<CustomOptions>(
+                    "custom",
+                    "set the KR needed for each level",
+                    SetLevelCurve(CurveKind::Custom),
+                )
+                .subcommand::<()>("show", "shows how much KR each level takes", ShowLevelCurve),
+        )
+        .group(
+            CommandSpec::new("announcements", "change how level ups are announced")
+                .subcommand::<()>(
+                    "same",
+                    "announce level ups where the user leveled up",
+                    SetAnnouncementTarget(TargetKind::Same),
+                )
+                .subcommand::<ChannelOptions>(
+                    "channel",
+                    "announce level ups in a channel",
+                    SetAnnouncementTarget(TargetKind::Channel),
+                )
+                .subcommand::<()>(
+                    "dm",
+                    "announce level ups in the user's DMs",
+                    SetAnnouncementTarget(TargetKind::Dm),
+                )
+                .subcommand::<()>(
+                    "off",
+                    "don't announce level ups",
+                    SetAnnouncementTarget(TargetKind::Off),
+                )
+                .subcommand::<TemplateOptions>(
+                    "message",
+                    "change the level up message",
+                    SetAnnouncementTemplate,
+                ),
+        )
+        .group(
+            CommandSpec::new("multipliers", "change how much KR messages are worth")
+                .subcommand::<ChannelMultiplierOptions>(
+                    "channel",
+                    "scale the KR of messages in a channel",
+                    SetMultiplier(multipliers.clone(), MultiplierKind::Channel),
+                )
+                .subcommand::<CategoryMultiplierOptions>(
+                    "category",
+                    "scale the KR of messages in a category",
+                    SetMultiplier(multipliers.clone(), MultiplierKind::Category),
+                )
+                .subcommand::<RoleMultiplierOptions>(
+                    "role",
+                    "scale the KR of messages by members with a role",
+                    SetMultiplier(multipliers.clone(), MultiplierKind::Role),
+                )
+                .subcommand::<RemoveMultiplierOptions>(
+                    "remove",
+                    "stop scaling the KR of a channel, category or role",
+                    RemoveMultiplier(multipliers.clone()),
+                )
+                .subcommand::<()>("list", "lists every multiplier", ListMultipliers),
+        )
+        .group(
+            CommandSpec::new("filters", "stop spam from giving KR")
+                .subcommand::<LengthOptions>(
+                    "length",
+                    "ignore messages shorter than a length",
+                    SetMinLength(filters.clone()),
+                )
+                .subcommand::<ToggleOptions>(
+                    "duplicates",
+                    "ignore messages similar to a member's recent messages",
+                    SetFilter(filters.clone(), FilterKind::Duplicates),
+                )
+                .subcommand::<ToggleOptions>(
+                    "emoji",
+                    "ignore messages that are only emoji or attachments",
+                    SetFilter(filters.clone(), FilterKind::EmojiOnly),
+                )
+                .subcommand::<CapOptions>(
+                    "cap",
+                    "limit how much KR members get in an hour",
+                    SetHourlyCap,
+                )
+                .subcommand::<()>("show", "shows which messages are ignored", ShowFilters),
+        )
+        .group(
+            CommandSpec::new("decay", "make inactive members lose KR over time")
+                .subcommand::<DecayOptions>(
+                    "set",
+                    "make inactive members lose KR every week",
+                    SetDecay,
+                )
+                .subcommand::<()>("off", "stop inactive members from losing KR", DisableDecay)
+                .subcommand::<()>("show", "shows how inactive members lose KR", ShowDecay),
+        )
+}
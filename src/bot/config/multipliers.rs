@@ -0,0 +1,229 @@
+//! Commands that change how much KR messages are worth.
+
+use crate::bot::xp::Multipliers;
+use crate::command::chat::Arguments;
+use crate::command::spec::Options;
+use crate::model::multiplier::{self, Target as MultiplierTarget};
+use crate::service::{Context, Error};
+use crate::{impl_command, options};
+
+use twilight_model::channel::ChannelType;
+use twilight_model::id::{ChannelId, RoleId};
+
+use std::fmt::Write;
+
+use anyhow::anyhow;
+
+options! {
+    /// Options for `/config multipliers channel`.
+    pub struct ChannelMultiplierOptions {
+        /// the channel to scale the KR of
+        channel: ChannelId,
+        /// how much KR to give, in percent of the usual amount, or 0 for none at all
+        percent: i64,
+    }
+}
+
+options! {
+    /// Options for `/config multipliers category`.
+    pub struct CategoryMultiplierOptions {
+        /// the category to scale the KR of
+        category: ChannelId,
+        /// how much KR to give, in percent of the usual amount, or 0 for none at all
+        percent: i64,
+    }
+}
+
+options! {
+    /// Options for `/config multipliers role`.
+    pub struct RoleMultiplierOptions {
+        /// the role to scale the KR of
+        role: RoleId,
+        /// how much KR to give, in percent of the usual amount, or 0 for none at all
+        percent: i64,
+    }
+}
+
+options! {
+    /// Options for `/config multipliers remove`.
+    pub struct RemoveMultiplierOptions {
+        /// the channel or category to stop scaling
+        channel: Option<ChannelId>,
+        /// the role to stop scaling
+        role: Option<RoleId>,
+    }
+}
+
+/// A kind of [`MultiplierTarget`] that can be picked with
+/// `/config multipliers`.
+#[derive(Clone, Copy, Debug)]
+pub enum MultiplierKind {
+    Channel,
+    Category,
+    Role,
+}
+
+/// The `/config multipliers` commands that set a multiplier.
+#[derive(Clone)]
+pub struct SetMultiplier(pub Multipliers, pub MultiplierKind);
+
+impl SetMultiplier {
+    /// The highest multiplier that can be set, in percent.
+    pub const MAX: i64 = 1000;
+}
+
+impl_command! {
+    impl Command for SetMultiplier {
+        async fn command(&self, cx: &Context, command: Arguments<'_>) -> Result<(), Error> {
+            let guild_id = command.guild_id().ok_or(anyhow!("guild_id is missing"))?;
+
+            let (target, percent) = match self.1 {
+                MultiplierKind::Channel => {
+                    let options = ChannelMultiplierOptions::parse(&command)?;
+
+                    (MultiplierTarget::Channel(options.channel), options.percent)
+                }
+                MultiplierKind::Category => {
+                    let options = CategoryMultiplierOptions::parse(&command)?;
+
+                    let is_category = command
+                        .get_channel("category")?
+                        .map(|channel| channel.kind == ChannelType::GuildCategory)
+                        .unwrap_or(false);
+
+                    if !is_category {
+                        return command
+                            .respond()
+                            .content("that isn't a category!")
+                            .ephemeral()
+                            .exec(cx.http())
+                            .await;
+                    }
+
+                    (MultiplierTarget::Category(options.category), options.percent)
+                }
+                MultiplierKind::Role => {
+                    let options = RoleMultiplierOptions::parse(&command)?;
+
+                    (MultiplierTarget::Role(options.role), options.percent)
+                }
+            };
+
+            let content = if (0..=Self::MAX).contains(&percent) {
+                // discord can't send numbers that aren't whole, so these are
+                // taken in percent
+                let value = percent as f64 / 100.;
+
+                multiplier::Guild::new(guild_id)
+                    .set(cx.db(), target, value)
+                    .await?;
+                self.0.invalidate(guild_id);
+
+                if percent == 0 {
+                    format!("messages in {} won't give any KR anymore!", target)
+                } else {
+                    format!("messages in {} now give {}× KR!", target, value)
+                }
+            } else {
+                format!("the multiplier has to be between 0% and {}%!", Self::MAX)
+            };
+
+            command
+                .respond()
+                .content(content)
+                .ephemeral()
+                .exec(cx.http())
+                .await
+        }
+    }
+}
+
+/// The `/config multipliers remove` command.
+#[derive(Clone)]
+pub struct RemoveMultiplier(pub Multipliers);
+
+impl_command! {
+    impl Command for RemoveMultiplier {
+        async fn command(&self, cx: &Context, command: Arguments<'_>) -> Result<(), Error> {
+            let guild_id = command.guild_id().ok_or(anyhow!("guild_id is missing"))?;
+
+            let options = RemoveMultiplierOptions::parse(&command)?;
+
+            // categories are channels too, so this removes either
+            let target = match (options.channel, options.role) {
+                (Some(channel_id), None) => MultiplierTarget::Channel(channel_id),
+                (None, Some(role_id)) => MultiplierTarget::Role(role_id),
+                _ => {
+                    return command
+                        .respond()
+                        .content("pick either a channel or a role!")
+                        .ephemeral()
+                        .exec(cx.http())
+                        .await;
+                }
+            };
+
+            let content = if multiplier::Guild::new(guild_id)
+                .remove(cx.db(), target)
+                .await?
+            {
+                self.0.invalidate(guild_id);
+
+                String::from("the multiplier was removed!")
+            } else {
+                String::from("there wasn't a multiplier for that!")
+            };
+
+            command
+                .respond()
+                .content(content)
+                .ephemeral()
+                .exec(cx.http())
+                .await
+        }
+    }
+}
+
+/// The `/config multipliers list` command.
+#[derive(Default, Clone)]
+pub struct ListMultipliers;
+
+impl_command! {
+    impl Command for ListMultipliers {
+        async fn command(&self, cx: &Context, command: Arguments<'_>) -> Result<(), Error> {
+            let guild_id = command.guild_id().ok_or(anyhow!("guild_id is missing"))?;
+
+            let multipliers = multiplier::Guild::new(guild_id)
+                .multipliers(cx.db())
+                .await?;
+
+            let content = if multipliers.is_empty() {
+                String::from("every message gives the same KR!")
+            } else {
+                let mut content = String::from(
+                    "messages give this much KR (multipliers that apply together are \
+                     multiplied):",
+                );
+
+                for multiplier in multipliers {
+                    write!(
+                        content,
+                        "\n• {}: {}×",
+                        multiplier.target(),
+                        multiplier.multiplier(),
+                    )
+                    .unwrap();
+                }
+
+                content
+            };
+
+            command
+                .respond()
+                .content(content)
+                .ephemeral()
+                .exec(cx.http())
+                .await
+        }
+    }
+}
@@ -0,0 +1,377 @@
+//! Commands that change who can use admin commands.
+
+use crate::command::chat::Arguments;
+use crate::command::spec::Options;
+use crate::model::permissions::{Guild, Owner};
+use crate::service::{Context, Error, Event};
+use crate::{impl_command, impl_service, options};
+
+use twilight_http::Client;
+use twilight_model::id::{GuildId, RoleId, UserId};
+
+use std::env;
+use std::fmt::{self, Display, Formatter, Write};
+use std::fs;
+use std::path::Path;
+use std::sync::Arc;
+
+use anyhow::anyhow;
+use serde::Deserialize;
+use sqlx::{postgres::Postgres, Executor};
+
+/// The most users and roles Discord lets use a command, not counting the
+/// permissions set through Discord itself.
+pub const MAX_OWNERS: usize = 10;
+
+/// The owners that may use admin commands in every guild.
+///
+/// These are set by the person hosting the bot, and can't be removed through
+/// `/config permissions`. This type is cheap to clone.
+///
+/// As a service, this applies the owners to every guild the bot joins or
+/// starts up in.
+#[derive(Clone, Debug, Default)]
+pub struct Owners(Arc<Vec<Owner>>);
+
+impl Owners {
+    /// Create a new `Owners`.
+    pub fn new(owners: Vec<Owner>) -> Owners {
+        Owners(Arc::new(owners))
+    }
+
+    /// Every owner of a guild, including the owners configured in the guild.
+    pub async fn get<'a, E>(&self, ex: E, guild_id: GuildId) -> Result<Vec<Owner>, Error>
+    where
+        E: Executor<'a, Database = Postgres>,
+    {
+        let mut owners = Guild::new(guild_id).owners(ex).await?;
+
+        for owner in self.0.iter() {
+            if !owners.contains(owner) {
+                owners.push(*owner);
+            }
+        }
+
+        Ok(owners)
+    }
+
+    /// Whether an owner is set by the person hosting the bot.
+    pub fn contains(&self, owner: &Owner) -> bool {
+        self.0.contains(owner)
+    }
+}
+
+impl_service! {
+    impl Service for Owners {
+        async fn handle(&self, cx: &Context, ev: &Event) -> Result<(), Error> {
+            if let Event::GuildCreate(guild) = ev {
+                let owners = self.get(cx.db(), guild.id).await?;
+
+                apply(cx.http(), guild.id, &owners).await?;
+            }
+
+            Ok(())
+        }
+    }
+}
+
+/// Reads owners from the `KROMER_ADMIN_USERS` and `KROMER_ADMIN_ROLES`
+/// environment variables, which are comma-seperated lists of ids.
+pub fn owners_from_env() -> Result<Vec<Owner>, Error> {
+    let mut owners = Vec::new();
+
+    for id in env_ids("KROMER_ADMIN_USERS")? {
+        owners.push(Owner::User(UserId(id)));
+    }
+
+    for id in env_ids("KROMER_ADMIN_ROLES")? {
+        owners.push(Owner::Role(RoleId(id)));
+    }
+
+    Ok(owners)
+}
+
+/// The owners file passed with `--owners`.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct OwnersFile {
+    #[serde(default)]
+    users: Vec<u64>,
+    #[serde(default)]
+    roles: Vec<u64>,
+}
+
+/// Reads owners from a TOML file, with a list of user ids under `users` and
+/// role ids under `roles`.
+pub fn owners_from_file(path: &Path) -> Result<Vec<Owner>, Error> {
+    let file = fs::read_to_string(path)
+        .map_err(|err| anyhow!("failed to read {}: {}", path.display(), err))?;
+
+    parse_owners(&file).map_err(|err| anyhow!("invalid owners file {}: {}", path.display(), err))
+}
+
+fn parse_owners(file: &str) -> Result<Vec<Owner>, toml::de::Error> {
+    let file = toml::from_str::<OwnersFile>(file)?;
+
+    Ok(file
+        .users
+        .into_iter()
+        .map(|id| Owner::User(UserId(id)))
+        .chain(file.roles.into_iter().map(|id| Owner::Role(RoleId(id))))
+        .collect())
+}
+
+fn env_ids(key: &str) -> Result<Vec<u64>, Error> {
+    match env::var(key) {
+        Ok(ids) => ids
+            .split(',')
+            .map(str::trim)
+            .filter(|id| !id.is_empty())
+            .map(|id| {
+                id.parse::<u64>()
+                    .map_err(|_| anyhow!("invalid id {:?} in {}", id, key))
+            })
+            .collect(),
+        Err(_) => Ok(Vec::new()),
+    }
+}
+
+/// Returned by [`apply`] when a guild has more than [`MAX_OWNERS`] owners.
+#[derive(Debug)]
+pub struct TooManyOwners(pub usize);
+
+impl Display for TooManyOwners {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} users and roles can use admin commands, but discord only allows {}",
+            self.0, MAX_OWNERS,
+        )
+    }
+}
+
+impl std::error::Error for TooManyOwners {}
+
+/// Grants every owner access to every admin command in a guild.
+///
+/// Admin commands are commands with `default_permission` disabled. This
+/// overwrites any permissions that were set through Discord itself.
+///
+/// Role owners are skipped if the role isn't in the guild, which is always
+/// the case for roles of other guilds set by the person hosting the bot.
+pub async fn apply(client: &Client, guild_id: GuildId, owners: &[Owner]) -> Result<(), Error> {
+    let roles = client.roles(guild_id).exec().await?.model().await?;
+
+    let owners = owners
+        .iter()
+        .filter(|owner| match owner {
+            Owner::User(_) => true,
+            Owner::Role(role_id) => roles.iter().any(|role| role.id == *role_id),
+        })
+        .collect::<Vec<_>>();
+
+    if owners.len() > MAX_OWNERS {
+        return Err(TooManyOwners(owners.len()).into());
+    }
+
+    // admin commands may be registered globally or in the guild
+    let mut commands = client.get_global_commands()?.exec().await?.model().await?;
+
+    commands.extend(
+        client
+            .get_guild_commands(guild_id)?
+            .exec()
+            .await?
+            .model()
+            .await?,
+    );
+
+    let permissions = commands
+        .iter()
+        .filter(|command| command.default_permission == Some(false))
+        .filter_map(|command| command.id)
+        .flat_map(|id| owners.iter().map(move |owner| (id, owner.permission())))
+        .collect::<Vec<_>>();
+
+    client
+        .set_command_permissions(guild_id, &permissions)?
+        .exec()
+        .await?;
+
+    Ok(())
+}
+
+options! {
+    /// Options for `/config permissions add` and `/config permissions remove`.
+    pub struct OwnerOptions {
+        /// the user to change
+        user: Option<UserId>,
+        /// the role to change
+        role: Option<RoleId>,
+    }
+}
+
+impl OwnerOptions {
+    fn owner(&self) -> Option<Owner> {
+        match (self.user, self.role) {
+            (Some(user_id), None) => Some(Owner::User(user_id)),
+            (None, Some(role_id)) => Some(Owner::Role(role_id)),
+            _ => None,
+        }
+    }
+}
+
+/// The `/config permissions add` command.
+#[derive(Clone)]
+pub struct AddOwner(pub Owners);
+
+impl_command! {
+    impl Command for AddOwner {
+        async fn command(&self, cx: &Context, command: Arguments<'_>) -> Result<(), Error> {
+            let guild_id = command.guild_id().ok_or(anyhow!("guild_id is missing"))?;
+
+            let owner = match OwnerOptions::parse(&command)?.owner() {
+                Some(owner) => owner,
+                None => {
+                    return command
+                        .respond()
+                        .content("pick either a user or a role!")
+                        .ephemeral()
+                        .exec(cx.http())
+                        .await;
+                }
+            };
+
+            // the owner is only saved if discord accepts the new permissions
+            let mut tx = cx.db().begin().await?;
+
+            let content = if Guild::new(guild_id).add(&mut tx, owner).await? {
+                let owners = self.0.get(&mut tx, guild_id).await?;
+
+                match apply(cx.http(), guild_id, &owners).await {
+                    Ok(()) => {
+                        tx.commit().await?;
+
+                        format!("{} can now use admin commands!", owner)
+                    }
+                    Err(err) if err.is::<TooManyOwners>() => format!(
+                        "only {} users and roles can use admin commands! remove some with \
+                         `/config permissions remove` first.",
+                        MAX_OWNERS,
+                    ),
+                    Err(err) => return Err(err),
+                }
+            } else {
+                format!("{} can already use admin commands!", owner)
+            };
+
+            command
+                .respond()
+                .content(content)
+                .ephemeral()
+                .exec(cx.http())
+                .await
+        }
+    }
+}
+
+/// The `/config permissions remove` command.
+#[derive(Clone)]
+pub struct RemoveOwner(pub Owners);
+
+impl_command! {
+    impl Command for RemoveOwner {
+        async fn command(&self, cx: &Context, command: Arguments<'_>) -> Result<(), Error> {
+            let guild_id = command.guild_id().ok_or(anyhow!("guild_id is missing"))?;
+
+            let owner = match OwnerOptions::parse(&command)?.owner() {
+                Some(owner) => owner,
+                None => {
+                    return command
+                        .respond()
+                        .content("pick either a user or a role!")
+                        .ephemeral()
+                        .exec(cx.http())
+                        .await;
+                }
+            };
+
+            let content = if self.0.contains(&owner) {
+                format!(
+                    "{} was made an admin by whoever is hosting me, so i can't \
+                     remove them!",
+                    owner,
+                )
+            } else {
+                // the owner is only removed if discord accepts the new permissions
+                let mut tx = cx.db().begin().await?;
+
+                if Guild::new(guild_id).remove(&mut tx, owner).await? {
+                    let owners = self.0.get(&mut tx, guild_id).await?;
+
+                    apply(cx.http(), guild_id, &owners).await?;
+                    tx.commit().await?;
+
+                    format!("{} can no longer use admin commands!", owner)
+                } else {
+                    format!("{} couldn't use admin commands anyways!", owner)
+                }
+            };
+
+            command
+                .respond()
+                .content(content)
+                .ephemeral()
+                .exec(cx.http())
+                .await
+        }
+    }
+}
+
+/// The `/config permissions list` command.
+#[derive(Clone)]
+pub struct ListOwners(pub Owners);
+
+impl_command! {
+    impl Command for ListOwners {
+        async fn command(&self, cx: &Context, command: Arguments<'_>) -> Result<(), Error> {
+            let guild_id = command.guild_id().ok_or(anyhow!("guild_id is missing"))?;
+
+            let owners = self.0.get(cx.db(), guild_id).await?;
+
+            let mut content = String::from("these users and roles can use admin commands:");
+
+            for owner in owners {
+                write!(content, "\n• {}", owner).unwrap();
+            }
+
+            command
+                .respond()
+                .content(content)
+                .ephemeral()
+                .exec(cx.http())
+                .await
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn owners_file() {
+        let owners = parse_owners("users = [1, 2]\nroles = [3]").unwrap();
+
+        assert_eq!(
+            owners,
+            [
+                Owner::User(UserId(1)),
+                Owner::User(UserId(2)),
+                Owner::Role(RoleId(3)),
+            ],
+        );
+        assert_eq!(parse_owners("").unwrap(), []);
+        assert!(parse_owners("user = [1]").is_err());
+    }
+}
@@ -1,12 +1,36 @@
 //! The actual services used by the bot.
 
+pub mod admin;
+pub mod config;
 pub mod info;
 pub mod roles;
 pub mod xp;
 
+use crate::command::spec::CommandSpec;
+use admin::PendingResets;
+use config::permissions::Owners;
+use xp::Xp;
+
 use twilight_http::Client;
 use twilight_model::id::ApplicationId;
 
+/// The definitions of every command the bot provides.
+///
+/// Commands that need shared state are given it here, so the same specs can
+/// be used to register the commands and route them.
+pub fn commands(owners: &Owners, xp: &Xp, resets: &PendingResets) -> Vec<CommandSpec> {
+    vec![
+        xp::RankCommand::spec(),
+        xp::TopCommand::spec(),
+        info::InfoCommand::spec(),
+        roles::reaction::spec(),
+        roles::menu::spec(),
+        roles::level::spec(),
+        config::spec(owners, xp.multipliers(), xp.filters()),
+        admin::spec(xp, resets),
+    ]
+}
+
 /// Gets the application id associated with the token.
 pub async fn fetch_application_id(client: &Client) -> Result<ApplicationId, anyhow::Error> {
     client
@@ -0,0 +1,347 @@
+//! Level role rewards.
+
+use super::{error_code, ignore_permissions_lacking};
+
+use crate::command::chat::Arguments;
+use crate::command::spec::{CommandSpec, Options};
+use crate::model::level;
+use crate::model::roles::level::{wanted, Guild, LevelRole, Mode};
+use crate::model::xp;
+use crate::service::{Context, Error};
+use crate::{impl_command, options};
+
+use twilight_http::api_error::ErrorCode;
+use twilight_http::request::AuditLogReason;
+
+use twilight_model::id::{GuildId, RoleId, UserId};
+
+use twilight_mention::Mention;
+
+use std::fmt::Write;
+use std::time::Duration;
+
+use anyhow::anyhow;
+
+/// Gives a member the level roles they've earned.
+///
+/// `roles` are the roles the member already has. In [`Mode::Replace`], level
+/// roles the member shouldn't have anymore are removed. Returns `true` if any
+/// roles were changed.
+pub async fn reward(
+    cx: &Context,
+    guild_id: GuildId,
+    user_id: UserId,
+    roles: &[RoleId],
+    level: i32,
+) -> Result<bool, Error> {
+    let guild = Guild::new(guild_id);
+
+    let level_roles = guild.roles(cx.db()).await?;
+
+    if level_roles.is_empty() {
+        return Ok(false);
+    }
+
+    let mode = guild.mode(cx.db()).await?;
+
+    apply(cx, guild_id, user_id, roles, &level_roles, mode, level).await
+}
+
+/// Like [`reward`], but with the level roles of the guild already fetched.
+async fn apply(
+    cx: &Context,
+    guild_id: GuildId,
+    user_id: UserId,
+    roles: &[RoleId],
+    level_roles: &[LevelRole],
+    mode: Mode,
+    level: i32,
+) -> Result<bool, Error> {
+    let wanted = wanted(level_roles, mode, level);
+
+    let mut changed = false;
+
+    for role_id in wanted.iter().filter(|role_id| !roles.contains(role_id)) {
+        let res = cx
+            .http()
+            .add_guild_member_role(guild_id, user_id, *role_id)
+            .reason("level role add")?
+            .exec()
+            .await;
+
+        ignore_permissions_lacking(res)?;
+        changed = true;
+    }
+
+    if mode == Mode::Replace {
+        let unwanted = level_roles
+            .iter()
+            .map(|role| role.role_id())
+            .filter(|role_id| roles.contains(role_id) && !wanted.contains(role_id));
+
+        for role_id in unwanted {
+            let res = cx
+                .http()
+                .remove_guild_member_role(guild_id, user_id, role_id)
+                .reason("level role remove")?
+                .exec()
+                .await;
+
+            ignore_permissions_lacking(res)?;
+            changed = true;
+        }
+    }
+
+    Ok(changed)
+}
+
+/// The definition of `/levelroles`.
+pub fn spec() -> CommandSpec {
+    CommandSpec::new("levelroles", "configure roles given for reaching levels")
+        .default_permission(false)
+        .subcommand::<AddOptions>(
+            "add",
+            "gives a role to users that reach a level",
+            AddLevelRole,
+        )
+        .subcommand::<RemoveOptions>(
+            "remove",
+            "stops giving a role for reaching a level",
+            RemoveLevelRole,
+        )
+        .subcommand::<()>(
+            "list",
+            "lists the roles given for reaching levels",
+            ListLevelRoles,
+        )
+        .subcommand::<ModeOptions>(
+            "mode",
+            "choose if users keep their lower level roles",
+            SetLevelRoleMode,
+        )
+        .subcommand::<()>(
+            "sync",
+            "gives level roles to users that already reached them",
+            SyncLevelRoles,
+        )
+}
+
+options! {
+    /// Options for `/levelroles add`.
+    pub struct AddOptions {
+        /// the level needed to get the role
+        level: i64,
+        /// the role to give
+        role: RoleId,
+    }
+}
+
+options! {
+    /// Options for `/levelroles remove`.
+    pub struct RemoveOptions {
+        /// the role to stop giving
+        role: RoleId,
+    }
+}
+
+options! {
+    /// Options for `/levelroles mode`.
+    pub struct ModeOptions {
+        /// whether to remove lower level roles when giving a higher one
+        replace: bool,
+    }
+}
+
+/// The `/levelroles add` command.
+#[derive(Default, Clone)]
+pub struct AddLevelRole;
+
+impl_command! {
+    impl Command for AddLevelRole {
+        async fn command(&self, cx: &Context, command: Arguments<'_>) -> Result<(), Error> {
+            let guild_id = command.guild_id().ok_or(anyhow!("guild_id is missing"))?;
+
+            let options = AddOptions::parse(&command)?;
+
+            let content = match i32::try_from(options.level) {
+                Ok(level) if level > 1 => {
+                    Guild::new(guild_id)
+                        .add(cx.db(), level, options.role)
+                        .await?;
+
+                    format!(
+                        "users that reach level {} will now get {}! use \
+                         `/levelroles sync` to give it to users that already \
+                         have.",
+                        level,
+                        options.role.mention(),
+                    )
+                }
+                _ => String::from("the level has to be at least 2!"),
+            };
+
+            command
+                .respond()
+                .content(content)
+                .ephemeral()
+                .exec(cx.http())
+                .await
+        }
+    }
+}
+
+/// The `/levelroles remove` command.
+#[derive(Default, Clone)]
+pub struct RemoveLevelRole;
+
+impl_command! {
+    impl Command for RemoveLevelRole {
+        async fn command(&self, cx: &Context, command: Arguments<'_>) -> Result<(), Error> {
+            let guild_id = command.guild_id().ok_or(anyhow!("guild_id is missing"))?;
+
+            let role_id = RemoveOptions::parse(&command)?.role;
+
+            let content = if Guild::new(guild_id).remove(cx.db(), role_id).await? {
+                format!("{} won't be given for reaching a level anymore!", role_id.mention())
+            } else {
+                format!("{} isn't given for reaching a level!", role_id.mention())
+            };
+
+            command
+                .respond()
+                .content(content)
+                .ephemeral()
+                .exec(cx.http())
+                .await
+        }
+    }
+}
+
+/// The `/levelroles list` command.
+#[derive(Default, Clone)]
+pub struct ListLevelRoles;
+
+impl_command! {
+    impl Command for ListLevelRoles {
+        async fn command(&self, cx: &Context, command: Arguments<'_>) -> Result<(), Error> {
+            let guild_id = command.guild_id().ok_or(anyhow!("guild_id is missing"))?;
+
+            let guild = Guild::new(guild_id);
+            let roles = guild.roles(cx.db()).await?;
+
+            let content = if roles.is_empty() {
+                String::from("no roles are given for reaching levels!")
+            } else {
+                let mut content = match guild.mode(cx.db()).await? {
+                    Mode::Stack => String::from("users keep every role they reach:"),
+                    Mode::Replace => String::from("users only keep their highest role:"),
+                };
+
+                for role in roles {
+                    write!(content, "\n• level {}: {}", role.level(), role.role_id().mention())
+                        .unwrap();
+                }
+
+                content
+            };
+
+            command
+                .respond()
+                .content(content)
+                .ephemeral()
+                .exec(cx.http())
+                .await
+        }
+    }
+}
+
+/// The `/levelroles mode` command.
+#[derive(Default, Clone)]
+pub struct SetLevelRoleMode;
+
+impl_command! {
+    impl Command for SetLevelRoleMode {
+        async fn command(&self, cx: &Context, command: Arguments<'_>) -> Result<(), Error> {
+            let guild_id = command.guild_id().ok_or(anyhow!("guild_id is missing"))?;
+
+            let (mode, content) = if ModeOptions::parse(&command)?.replace {
+                (Mode::Replace, "users will now only keep their highest level role!")
+            } else {
+                (Mode::Stack, "users will now keep every level role they reach!")
+            };
+
+            Guild::new(guild_id).set_mode(cx.db(), mode).await?;
+
+            command
+                .respond()
+                .content(content)
+                .ephemeral()
+                .exec(cx.http())
+                .await
+        }
+    }
+}
+
+/// The `/levelroles sync` command.
+#[derive(Default, Clone)]
+pub struct SyncLevelRoles;
+
+impl_command! {
+    impl Command for SyncLevelRoles {
+        async fn command(&self, cx: &Context, command: Arguments<'_>) -> Result<(), Error> {
+            let guild_id = command.guild_id().ok_or(anyhow!("guild_id is missing"))?;
+
+            // this goes through a lot of members
+            let _defer = command.defer_after(cx.http(), Duration::from_secs(2), true);
+
+            let guild = Guild::new(guild_id);
+            let roles = guild.roles(cx.db()).await?;
+            let mode = guild.mode(cx.db()).await?;
+            let curve = level::Guild::new(guild_id).curve(cx.db()).await?;
+
+            // only look at users that reached the lowest level role
+            let lowest = roles
+                .first()
+                .and_then(|role| curve.score_for(role.level()));
+
+            let records = match lowest {
+                Some(score) => xp::Guild::new(guild_id).at_least(cx.db(), score).await?,
+                None => Vec::new(),
+            };
+
+            let mut synced = 0;
+
+            for record in records {
+                let res = cx
+                    .http()
+                    .guild_member(guild_id, record.user_id())
+                    .exec()
+                    .await;
+
+                let member = match res {
+                    Ok(res) => res.model().await?,
+                    // the user left the guild
+                    Err(err) if matches!(error_code(&err), Some(ErrorCode::UnknownMember)) => {
+                        continue
+                    }
+                    Err(err) => return Err(err.into()),
+                };
+
+                let level = record.level(&curve);
+
+                let user_id = record.user_id();
+
+                if apply(cx, guild_id, user_id, &member.roles, &roles, mode, level).await? {
+                    synced += 1;
+                }
+            }
+
+            command
+                .respond()
+                .content(format!("updated the level roles of {} members!", synced))
+                .ephemeral()
+                .exec(cx.http())
+                .await
+        }
+    }
+}
@@ -0,0 +1,656 @@
+//! Role menus made of buttons or select menus.
+
+use super::{error_code, parse_emoji, reaction_type};
+
+use crate::command::chat::Arguments;
+use crate::command::component::{
+    button, custom_id, select_menu, select_option, Arguments as ComponentArguments,
+};
+use crate::command::spec::{CommandSpec, Options};
+use crate::model::roles::menu::{Guild, Kind, MenuRole, RoleMenu};
+use crate::service::{Context, Error};
+use crate::{choices, impl_command, impl_component, options, state};
+
+use twilight_http::api_error::ErrorCode;
+use twilight_http::request::AuditLogReason;
+
+use twilight_model::application::component::{
+    button::ButtonStyle, ActionRow, Component as TwilightComponent,
+};
+use twilight_model::channel::message::AllowedMentions;
+use twilight_model::id::{ChannelId, GuildId, RoleId, UserId};
+
+use twilight_mention::Mention;
+
+use std::fmt::Write;
+
+use anyhow::anyhow;
+
+/// The most roles a menu can have.
+///
+/// Messages can have 5 rows of 5 buttons, and select menus can have 25
+/// options.
+pub const MAX_ROLES: usize = 25;
+
+/// The longest a button or option label can be.
+pub const MAX_LABEL: usize = 80;
+
+/// The definition of `/rolemenu`.
+pub fn spec() -> CommandSpec {
+    CommandSpec::new("rolemenu", "configure menus that let members pick roles")
+        .default_permission(false)
+        .subcommand::<CreateOptions>("create", "posts a new role menu", CreateRoleMenu)
+        .subcommand::<AddOptions>("add", "adds a role to a role menu", AddMenuRole)
+        .subcommand::<RemoveOptions>("remove", "removes a role from a role menu", RemoveMenuRole)
+        .subcommand::<EditOptions>(
+            "edit",
+            "changes the message or style of a role menu",
+            EditRoleMenu,
+        )
+        .subcommand::<MenuOptions>("delete", "deletes a role menu", DeleteRoleMenu)
+        .subcommand::<()>("list", "lists every role menu in the server", ListRoleMenus)
+}
+
+choices! {
+    /// A [`Kind`] picked in a command.
+    pub enum KindChoice {
+        /// buttons: a button for every role
+        Buttons = "buttons",
+        /// select: a menu to pick every role wanted
+        Select = "select",
+    }
+}
+
+impl From<KindChoice> for Kind {
+    fn from(choice: KindChoice) -> Kind {
+        match choice {
+            KindChoice::Buttons => Kind::Buttons,
+            KindChoice::Select => Kind::Select,
+        }
+    }
+}
+
+options! {
+    /// Options for `/rolemenu create`.
+    pub struct CreateOptions {
+        /// the channel to post the menu in
+        channel: ChannelId,
+        /// the message to show above the menu
+        message: String,
+        /// how members pick roles; defaults to buttons
+        style: Option<KindChoice>,
+    }
+}
+
+options! {
+    /// Options for `/rolemenu add`.
+    pub struct AddOptions {
+        /// the id of the menu
+        menu: i64,
+        /// the role to add
+        role: RoleId,
+        /// the text shown for the role; defaults to the name of the role
+        label: Option<String>,
+        /// an emoji shown next to the role
+        emoji: Option<String>,
+    }
+}
+
+options! {
+    /// Options for `/rolemenu remove`.
+    pub struct RemoveOptions {
+        /// the id of the menu
+        menu: i64,
+        /// the role to remove
+        role: RoleId,
+    }
+}
+
+options! {
+    /// Options for `/rolemenu edit`.
+    pub struct EditOptions {
+        /// the id of the menu
+        menu: i64,
+        /// the new message to show above the menu
+        message: Option<String>,
+        /// how members pick roles
+        style: Option<KindChoice>,
+    }
+}
+
+options! {
+    /// Options for `/rolemenu delete`.
+    pub struct MenuOptions {
+        /// the id of the menu
+        menu: i64,
+    }
+}
+
+state! {
+    /// The state of role menu buttons and select menus.
+    pub struct RoleMenuState {
+        /// The role of the button, or `None` for a select menu.
+        role_id: Option<RoleId>,
+    }
+}
+
+/// Creates the buttons or select menu of a role menu.
+fn components(kind: Kind, roles: &[MenuRole]) -> Vec<TwilightComponent> {
+    if roles.is_empty() {
+        return Vec::new();
+    }
+
+    match kind {
+        Kind::Buttons => roles
+            .chunks(5)
+            .map(|row| {
+                let components = row
+                    .iter()
+                    .map(|role| {
+                        let state = RoleMenuState {
+                            role_id: Some(role.role_id()),
+                        };
+
+                        let mut button = button(
+                            ButtonStyle::Secondary,
+                            custom_id("rolemenu", &state),
+                            role.label(),
+                        );
+                        button.emoji = role.emoji().and_then(reaction_type);
+
+                        TwilightComponent::Button(button)
+                    })
+                    .collect();
+
+                TwilightComponent::ActionRow(ActionRow { components })
+            })
+            .collect(),
+        Kind::Select => {
+            let options = roles
+                .iter()
+                .map(|role| {
+                    let mut option = select_option(role.label(), role.role_id().to_string());
+                    option.emoji = role.emoji().and_then(reaction_type);
+
+                    option
+                })
+                .collect();
+
+            let mut menu = select_menu(
+                custom_id("rolemenu", &RoleMenuState { role_id: None }),
+                options,
+            );
+            menu.placeholder = Some(String::from("pick your roles"));
+            menu.min_values = Some(0);
+            menu.max_values = Some(roles.len() as u8);
+
+            let components = vec![TwilightComponent::SelectMenu(menu)];
+
+            vec![TwilightComponent::ActionRow(ActionRow { components })]
+        }
+    }
+}
+
+/// Updates the posted message of a role menu.
+///
+/// Returns `false` if the message was deleted.
+async fn refresh(cx: &Context, guild: &Guild, menu: &RoleMenu) -> Result<bool, Error> {
+    let roles = guild.roles(cx.db(), menu.id()).await?;
+    let components = components(menu.kind(), &roles);
+
+    let res = cx
+        .http()
+        .update_message(menu.channel_id(), menu.message_id())
+        .content(Some(menu.content()))?
+        .components(Some(&components))?
+        .exec()
+        .await;
+
+    match res {
+        Ok(_) => Ok(true),
+        Err(err) if matches!(error_code(&err), Some(ErrorCode::UnknownMessage)) => Ok(false),
+        Err(err) => Err(err.into()),
+    }
+}
+
+/// The response for a menu whose message was deleted.
+const DELETED: &str = "the message of that menu was deleted! use `/rolemenu delete` to clean \
+                       it up";
+
+/// The response for a menu that doesn't exist.
+const UNKNOWN: &str = "there isn't a menu with that id! use `/rolemenu list` to find it";
+
+/// The `/rolemenu create` command.
+#[derive(Default, Clone)]
+pub struct CreateRoleMenu;
+
+impl_command! {
+    impl Command for CreateRoleMenu {
+        async fn command(&self, cx: &Context, command: Arguments<'_>) -> Result<(), Error> {
+            let guild_id = command.guild_id().ok_or(anyhow!("guild_id is missing"))?;
+
+            let options = CreateOptions::parse(&command)?;
+            let kind = options.style.map(Kind::from).unwrap_or(Kind::Buttons);
+
+            // the menu is empty until roles are added
+            let res = cx
+                .http()
+                .create_message(options.channel)
+                .content(&options.message)?
+                .allowed_mentions(AllowedMentions::default())
+                .exec()
+                .await;
+
+            let message = match res {
+                Ok(res) => res.model().await?,
+                Err(err) if matches!(error_code(&err), Some(ErrorCode::PermissionsLacking)) => {
+                    return command
+                        .respond()
+                        .content(format!(
+                            "i'm not allowed to post in {}!",
+                            options.channel.mention()
+                        ))
+                        .ephemeral()
+                        .exec(cx.http())
+                        .await;
+                }
+                Err(err) => return Err(err.into()),
+            };
+
+            let menu = Guild::new(guild_id)
+                .create(cx.db(), options.channel, message.id, kind, &options.message)
+                .await?;
+
+            command
+                .respond()
+                .content(format!(
+                    "role menu {} posted in {}! add roles to it with `/rolemenu add`",
+                    menu.id(),
+                    options.channel.mention(),
+                ))
+                .ephemeral()
+                .exec(cx.http())
+                .await
+        }
+    }
+}
+
+/// The `/rolemenu add` command.
+#[derive(Default, Clone)]
+pub struct AddMenuRole;
+
+impl_command! {
+    impl Command for AddMenuRole {
+        async fn command(&self, cx: &Context, command: Arguments<'_>) -> Result<(), Error> {
+            let guild_id = command.guild_id().ok_or(anyhow!("guild_id is missing"))?;
+
+            let options = AddOptions::parse(&command)?;
+
+            let label = match options.label {
+                Some(label) => label,
+                None => command
+                    .get_role("role")?
+                    .map(|role| role.name.clone())
+                    .unwrap_or_else(|| options.role.to_string()),
+            };
+
+            let emoji = match options.emoji.as_deref().map(parse_emoji) {
+                Some(Some(emoji)) => Some(emoji),
+                Some(None) => {
+                    return command
+                        .respond()
+                        .content("that isn't an emoji!")
+                        .ephemeral()
+                        .exec(cx.http())
+                        .await;
+                }
+                None => None,
+            };
+
+            let guild = Guild::new(guild_id);
+
+            let content = if label.is_empty() || label.chars().count() > MAX_LABEL {
+                format!("the label has to be between 1 and {} characters!", MAX_LABEL)
+            } else {
+                match guild.menu(cx.db(), options.menu).await? {
+                    Some(menu) => {
+                        let roles = guild.roles(cx.db(), menu.id()).await?;
+
+                        let exists = roles.iter().any(|role| role.role_id() == options.role);
+
+                        if !exists && roles.len() >= MAX_ROLES {
+                            format!("a menu can have at most {} roles!", MAX_ROLES)
+                        } else {
+                            guild
+                                .add_role(cx.db(), menu.id(), options.role, &label, emoji.as_ref())
+                                .await?;
+
+                            if refresh(cx, &guild, &menu).await? {
+                                format!("{} was added to the menu!", options.role.mention())
+                            } else {
+                                String::from(DELETED)
+                            }
+                        }
+                    }
+                    None => String::from(UNKNOWN),
+                }
+            };
+
+            command
+                .respond()
+                .content(content)
+                .ephemeral()
+                .exec(cx.http())
+                .await
+        }
+    }
+}
+
+/// The `/rolemenu remove` command.
+#[derive(Default, Clone)]
+pub struct RemoveMenuRole;
+
+impl_command! {
+    impl Command for RemoveMenuRole {
+        async fn command(&self, cx: &Context, command: Arguments<'_>) -> Result<(), Error> {
+            let guild_id = command.guild_id().ok_or(anyhow!("guild_id is missing"))?;
+
+            let options = RemoveOptions::parse(&command)?;
+
+            let guild = Guild::new(guild_id);
+
+            let content = match guild.menu(cx.db(), options.menu).await? {
+                Some(menu) => {
+                    if guild.remove_role(cx.db(), menu.id(), options.role).await? {
+                        if refresh(cx, &guild, &menu).await? {
+                            format!("{} was removed from the menu!", options.role.mention())
+                        } else {
+                            String::from(DELETED)
+                        }
+                    } else {
+                        format!("{} isn't in that menu!", options.role.mention())
+                    }
+                }
+                None => String::from(UNKNOWN),
+            };
+
+            command
+                .respond()
+                .content(content)
+                .ephemeral()
+                .exec(cx.http())
+                .await
+        }
+    }
+}
+
+/// The `/rolemenu edit` command.
+#[derive(Default, Clone)]
+pub struct EditRoleMenu;
+
+impl_command! {
+    impl Command for EditRoleMenu {
+        async fn command(&self, cx: &Context, command: Arguments<'_>) -> Result<(), Error> {
+            let guild_id = command.guild_id().ok_or(anyhow!("guild_id is missing"))?;
+
+            let options = EditOptions::parse(&command)?;
+
+            let guild = Guild::new(guild_id);
+
+            let menu = guild
+                .edit(
+                    cx.db(),
+                    options.menu,
+                    options.style.map(Kind::from),
+                    options.message.as_deref(),
+                )
+                .await?;
+
+            let content = match menu {
+                Some(menu) => {
+                    if refresh(cx, &guild, &menu).await? {
+                        "the menu was updated!"
+                    } else {
+                        DELETED
+                    }
+                }
+                None => UNKNOWN,
+            };
+
+            command
+                .respond()
+                .content(content)
+                .ephemeral()
+                .exec(cx.http())
+                .await
+        }
+    }
+}
+
+/// The `/rolemenu delete` command.
+#[derive(Default, Clone)]
+pub struct DeleteRoleMenu;
+
+impl_command! {
+    impl Command for DeleteRoleMenu {
+        async fn command(&self, cx: &Context, command: Arguments<'_>) -> Result<(), Error> {
+            let guild_id = command.guild_id().ok_or(anyhow!("guild_id is missing"))?;
+
+            let id = MenuOptions::parse(&command)?.menu;
+
+            let guild = Guild::new(guild_id);
+
+            let content = match guild.menu(cx.db(), id).await? {
+                Some(menu) => {
+                    let res = cx
+                        .http()
+                        .delete_message(menu.channel_id(), menu.message_id())
+                        .exec()
+                        .await;
+
+                    match res {
+                        Ok(_) => (),
+                        // it's already gone
+                        Err(err)
+                            if matches!(error_code(&err), Some(ErrorCode::UnknownMessage)) => {}
+                        Err(err) => return Err(err.into()),
+                    }
+
+                    guild.delete(cx.db(), id).await?;
+
+                    "the menu was deleted!"
+                }
+                None => UNKNOWN,
+            };
+
+            command
+                .respond()
+                .content(content)
+                .ephemeral()
+                .exec(cx.http())
+                .await
+        }
+    }
+}
+
+/// The `/rolemenu list` command.
+#[derive(Default, Clone)]
+pub struct ListRoleMenus;
+
+impl_command! {
+    impl Command for ListRoleMenus {
+        async fn command(&self, cx: &Context, command: Arguments<'_>) -> Result<(), Error> {
+            let guild_id = command.guild_id().ok_or(anyhow!("guild_id is missing"))?;
+
+            let guild = Guild::new(guild_id);
+            let menus = guild.menus(cx.db()).await?;
+
+            let content = if menus.is_empty() {
+                String::from("there aren't any role menus! post one with `/rolemenu create`")
+            } else {
+                let mut content = String::from("role menus in this server:");
+
+                for menu in menus {
+                    let roles = guild.roles(cx.db(), menu.id()).await?;
+
+                    write!(
+                        content,
+                        "\n\n**menu {}** ([message](https://discord.com/channels/{}/{}/{})) \
+                         in {}:",
+                        menu.id(),
+                        menu.guild_id(),
+                        menu.channel_id(),
+                        menu.message_id(),
+                        menu.channel_id().mention(),
+                    )
+                    .unwrap();
+
+                    if roles.is_empty() {
+                        content.push_str(" no roles yet");
+                    }
+
+                    for role in roles {
+                        write!(content, "\n• {}", role.role_id().mention()).unwrap();
+                    }
+                }
+
+                content
+            };
+
+            command
+                .respond()
+                .content(content)
+                .ephemeral()
+                .exec(cx.http())
+                .await
+        }
+    }
+}
+
+/// Handles clicks on role menus.
+#[derive(Default, Clone)]
+pub struct RoleMenuComponents;
+
+impl_component! {
+    impl Component for RoleMenuComponents {
+        async fn component(
+            &self,
+            cx: &Context,
+            component: ComponentArguments<'_>,
+        ) -> Result<(), Error> {
+            let guild_id = component.guild_id().ok_or(anyhow!("guild_id is missing"))?;
+
+            let guild = Guild::new(guild_id);
+
+            let menu = match guild.by_message(cx.db(), component.message().id).await? {
+                Some(menu) => menu,
+                None => {
+                    return component
+                        .respond()
+                        .content("this menu doesn't exist anymore!")
+                        .ephemeral()
+                        .exec(cx.http())
+                        .await;
+                }
+            };
+
+            let roles = guild.roles(cx.db(), menu.id()).await?;
+            let user_id = component.user_id();
+            let has = component.roles();
+
+            // figure out which of the menu's roles the user wants
+            let wanted = match component.state::<RoleMenuState>()?.role_id {
+                // buttons toggle one role
+                Some(role_id) => roles
+                    .iter()
+                    .map(MenuRole::role_id)
+                    .filter(|id| (*id == role_id) != has.contains(id))
+                    .collect::<Vec<_>>(),
+                // select menus pick every role
+                None => component
+                    .values()
+                    .iter()
+                    .filter_map(|value| value.parse().ok().map(RoleId))
+                    .collect(),
+            };
+
+            let mut added = Vec::new();
+            let mut removed = Vec::new();
+            let mut failed = Vec::new();
+
+            for role_id in roles.iter().map(MenuRole::role_id) {
+                let want = wanted.contains(&role_id);
+
+                if want == has.contains(&role_id) {
+                    continue;
+                }
+
+                // keep going, so the other roles still change
+                let changed = if set_role(cx, guild_id, user_id, role_id, want).await? {
+                    if want {
+                        &mut added
+                    } else {
+                        &mut removed
+                    }
+                } else {
+                    &mut failed
+                };
+
+                changed.push(role_id.mention().to_string());
+            }
+
+            let mut content = match (added.is_empty(), removed.is_empty()) {
+                (true, true) => String::from("your roles didn't change!"),
+                (false, true) => format!("gave you {}!", added.join(", ")),
+                (true, false) => format!("took away {}!", removed.join(", ")),
+                (false, false) => format!(
+                    "gave you {} and took away {}!",
+                    added.join(", "),
+                    removed.join(", "),
+                ),
+            };
+
+            if !failed.is_empty() {
+                write!(content, "\ni'm not allowed to manage {}!", failed.join(", ")).unwrap();
+            }
+
+            component
+                .respond()
+                .content(content)
+                .ephemeral()
+                .exec(cx.http())
+                .await
+        }
+    }
+}
+
+/// Gives or takes away a role.
+///
+/// Returns `false` if the bot isn't allowed to.
+async fn set_role(
+    cx: &Context,
+    guild_id: GuildId,
+    user_id: UserId,
+    role_id: RoleId,
+    give: bool,
+) -> Result<bool, Error> {
+    let res = if give {
+        cx.http()
+            .add_guild_member_role(guild_id, user_id, role_id)
+            .reason("role menu add")?
+            .exec()
+            .await
+    } else {
+        cx.http()
+            .remove_guild_member_role(guild_id, user_id, role_id)
+            .reason("role menu remove")?
+            .exec()
+            .await
+    };
+
+    match res {
+        Ok(_) => Ok(true),
+        Err(err) if matches!(error_code(&err), Some(ErrorCode::PermissionsLacking)) => Ok(false),
+        Err(err) => Err(err.into()),
+    }
+}
@@ -1,3 +1,65 @@
 //! Role-related services.
 
+pub mod level;
+pub mod menu;
 pub mod reaction;
+
+use crate::model::Emoji;
+use crate::service::Error;
+
+use twilight_model::channel::ReactionType;
+use twilight_model::id::EmojiId;
+
+use twilight_http::api_error::{ApiError, ErrorCode};
+use twilight_http::error::{Error as HttpError, ErrorType};
+
+/// The error code Discord responded with, if any.
+pub fn error_code(err: &HttpError) -> Option<&ErrorCode> {
+    match err.kind() {
+        ErrorType::Response {
+            error: ApiError::General(api_err),
+            ..
+        } => Some(&api_err.code),
+        _ => None,
+    }
+}
+
+/// Silently discards errors caused by the bot not being allowed to manage a
+/// role.
+pub fn ignore_permissions_lacking<T>(res: Result<T, HttpError>) -> Result<(), Error> {
+    match res {
+        Ok(_) => Ok(()),
+        Err(err) if matches!(error_code(&err), Some(ErrorCode::PermissionsLacking)) => Ok(()),
+        Err(err) => Err(err.into()),
+    }
+}
+
+/// Gets an emoji from a unicode emoji or a custom emoji like `<:name:id>`.
+pub fn parse_emoji(emoji: &str) -> Option<Emoji> {
+    let emoji = emoji.trim();
+
+    if emoji.is_empty() {
+        return None;
+    }
+
+    match emoji.strip_prefix('<').and_then(|e| e.strip_suffix('>')) {
+        Some(custom) => custom.rsplit(':').next()?.parse().ok().map(Emoji::Custom),
+        None => Some(Emoji::Unicode(String::from(emoji))),
+    }
+}
+
+/// Gets an emoji that can be shown on a button or select menu option.
+///
+/// Returns `None` for [`Emoji::Legacy`], since we don't know what it is
+/// exactly.
+pub fn reaction_type(emoji: &Emoji) -> Option<ReactionType> {
+    match emoji {
+        Emoji::Unicode(name) => Some(ReactionType::Unicode { name: name.clone() }),
+        Emoji::Custom(id) => Some(ReactionType::Custom {
+            animated: false,
+            id: EmojiId(*id),
+            name: None,
+        }),
+        Emoji::Legacy(_) => None,
+    }
+}
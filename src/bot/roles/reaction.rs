@@ -1,31 +1,49 @@
 //! Reaction role services.
 
+use super::{error_code, ignore_permissions_lacking, parse_emoji, reaction_type};
+
 use crate::command::chat::Arguments;
-use crate::impl_service;
-use crate::model::roles::reaction::{Message, ReactionRole};
+use crate::command::component::{
+    custom_id, select_menu, select_option, Arguments as ComponentArguments,
+};
+use crate::command::spec::{CommandSpec, Options};
+use crate::model::roles::reaction::{Guild, Message, Mode, ReactionRole};
 use crate::model::Emoji;
-use crate::service::{Context, Error, Service};
+use crate::service::{Context, Error};
+use crate::{choices, impl_command, impl_component, impl_service, options, state};
 
-use twilight_http::api_error::{ApiError, ErrorCode};
+use twilight_http::api_error::ErrorCode;
+use twilight_http::request::channel::reaction::RequestReactionType;
 use twilight_http::request::AuditLogReason;
 
-use twilight_model::application::interaction::Interaction;
-use twilight_model::channel::Reaction;
+use twilight_model::application::component::Component as TwilightComponent;
+use twilight_model::channel::{Channel, Reaction};
 use twilight_model::gateway::event::Event;
-use twilight_model::id::RoleId;
+use twilight_model::id::{EmojiId, GuildId, MessageId, RoleId, UserId};
 
 use twilight_mention::Mention;
 
 use tokio::select;
 use tokio::time::sleep;
 
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::sync::Arc;
 use std::time::Duration;
 
 use anyhow::anyhow;
+use dashmap::DashMap;
 
 /// Reaction role service.
 #[derive(Default, Clone)]
-pub struct ReactionRoles;
+pub struct ReactionRoles {
+    /// The custom emojis of every guild, to find out which were deleted when
+    /// they change.
+    ///
+    /// Reaction roles can use emojis from other guilds, so only emojis that
+    /// were known to be in the guild are ever cleaned up.
+    emojis: Arc<DashMap<GuildId, Vec<EmojiId>>>,
+}
 
 impl ReactionRoles {
     async fn reaction_add(&self, cx: &Context, reaction: &Reaction) -> Result<(), Error> {
@@ -35,33 +53,46 @@ impl ReactionRoles {
             None => return Ok(()),
         };
 
-        match self.get_reaction_role(cx, reaction).await? {
+        let rr = match self.get_reaction_role(cx, reaction).await? {
             // this is a reaction for a role!
-            Some(rr) => {
-                let res = cx
-                    .http()
-                    .add_guild_member_role(guild_id, reaction.user_id, rr.role_id())
-                    .reason("reaction role add")?
-                    .exec()
-                    .await;
+            Some(rr) => rr,
+            // this is just a normal reaction
+            None => return Ok(()),
+        };
+
+        let user_id = reaction.user_id;
+
+        match rr.mode() {
+            Mode::Normal | Mode::Verify => add_role(cx, guild_id, user_id, rr.role_id()).await,
+            Mode::Drop => remove_role(cx, guild_id, user_id, rr.role_id()).await,
+            Mode::Unique => {
+                add_role(cx, guild_id, user_id, rr.role_id()).await?;
+
+                self.remove_others(cx, guild_id, reaction, &rr).await
+            }
+            Mode::Toggle => {
+                let roles = match &reaction.member {
+                    Some(member) => member.roles.clone(),
+                    None => {
+                        cx.http()
+                            .guild_member(guild_id, user_id)
+                            .exec()
+                            .await?
+                            .model()
+                            .await?
+                            .roles
+                    }
+                };
 
-                match res {
-                    Ok(_) => Ok(()),
-                    Err(err) => match err.kind() {
-                        twilight_http::error::ErrorType::Response {
-                            error: ApiError::General(api_err),
-                            ..
-                        } => match api_err.code {
-                            // silently discard permissions lacking errors
-                            ErrorCode::PermissionsLacking => Ok(()),
-                            _ => Err(err.into()),
-                        },
-                        _ => Err(err.into()),
-                    },
+                if roles.contains(&rr.role_id()) {
+                    remove_role(cx, guild_id, user_id, rr.role_id()).await?;
+                } else {
+                    add_role(cx, guild_id, user_id, rr.role_id()).await?;
                 }
+
+                // take the reaction back so it can be used again
+                remove_reaction(cx, reaction, rr.emoji()).await
             }
-            // this is just a normal reaction
-            None => Ok(()),
         }
     }
 
@@ -74,34 +105,116 @@ impl ReactionRoles {
 
         match self.get_reaction_role(cx, reaction).await? {
             // this is a reaction for a role!
-            Some(rr) => {
-                let res = cx
-                    .http()
-                    .remove_guild_member_role(guild_id, reaction.user_id, rr.role_id())
-                    .reason("reaction role remove")?
-                    .exec()
-                    .await;
-
-                match res {
-                    Ok(_) => Ok(()),
-                    Err(err) => match err.kind() {
-                        twilight_http::error::ErrorType::Response {
-                            error: ApiError::General(api_err),
-                            ..
-                        } => match api_err.code {
-                            // silently discard permissions lacking errors
-                            ErrorCode::PermissionsLacking => Ok(()),
-                            _ => Err(err.into()),
-                        },
-                        _ => Err(err.into()),
-                    },
+            Some(rr) => match rr.mode() {
+                Mode::Normal | Mode::Unique => {
+                    remove_role(cx, guild_id, reaction.user_id, rr.role_id()).await
                 }
-            }
+                // these don't care about unreacting
+                Mode::Verify | Mode::Drop | Mode::Toggle => Ok(()),
+            },
             // this is just a normal reaction
             None => Ok(()),
         }
     }
 
+    /// Takes away the other unique roles on a message, and their reactions.
+    async fn remove_others(
+        &self,
+        cx: &Context,
+        guild_id: GuildId,
+        reaction: &Reaction,
+        picked: &ReactionRole,
+    ) -> Result<(), Error> {
+        let rrs = Guild::new(guild_id)
+            .by_message(cx.db(), reaction.message_id)
+            .await?;
+
+        let roles = reaction.member.as_ref().map(|member| &member.roles);
+
+        let others = rrs.iter().filter(|rr| {
+            rr.mode() == Mode::Unique
+                && rr.emoji() != picked.emoji()
+                && rr.role_id() != picked.role_id()
+        });
+
+        for rr in others {
+            // don't bother removing roles they don't have
+            if roles
+                .map(|roles| roles.contains(&rr.role_id()))
+                .unwrap_or(true)
+            {
+                remove_role(cx, guild_id, reaction.user_id, rr.role_id()).await?;
+            }
+
+            remove_reaction(cx, reaction, rr.emoji()).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Removes the reaction roles pointing at something that was deleted, and
+    /// keeps track of the emojis of each guild to tell when one is.
+    async fn clean_up(&self, cx: &Context, ev: &Event) -> Result<(), Error> {
+        match ev {
+            Event::MessageDelete(ev) => {
+                if let Some(guild_id) = ev.guild_id {
+                    Guild::new(guild_id)
+                        .remove_messages(cx.db(), &[ev.id])
+                        .await?;
+                }
+            }
+            Event::MessageDeleteBulk(ev) => {
+                if let Some(guild_id) = ev.guild_id {
+                    Guild::new(guild_id)
+                        .remove_messages(cx.db(), &ev.ids)
+                        .await?;
+                }
+            }
+            Event::ChannelDelete(ev) => {
+                if let Channel::Guild(channel) = &ev.0 {
+                    if let Some(guild_id) = channel.guild_id() {
+                        Guild::new(guild_id)
+                            .remove_channel(cx.db(), channel.id())
+                            .await?;
+                    }
+                }
+            }
+            Event::RoleDelete(ev) => {
+                Guild::new(ev.guild_id)
+                    .remove_role(cx.db(), ev.role_id)
+                    .await?;
+            }
+            Event::GuildCreate(guild) => {
+                let emoji_ids = guild.emojis.iter().map(|emoji| emoji.id).collect();
+
+                self.emojis.insert(guild.id, emoji_ids);
+            }
+            Event::GuildDelete(guild) => {
+                self.emojis.remove(&guild.id);
+            }
+            Event::GuildEmojisUpdate(ev) => {
+                let emoji_ids = ev.emojis.iter().map(|emoji| emoji.id).collect::<Vec<_>>();
+
+                let deleted = match self.emojis.insert(ev.guild_id, emoji_ids.clone()) {
+                    Some(old) => old
+                        .into_iter()
+                        .filter(|id| !emoji_ids.contains(id))
+                        .collect::<Vec<_>>(),
+                    None => Vec::new(),
+                };
+
+                if !deleted.is_empty() {
+                    Guild::new(ev.guild_id)
+                        .remove_emojis(cx.db(), &deleted)
+                        .await?;
+                }
+            }
+            _ => (),
+        }
+
+        Ok(())
+    }
+
     async fn get_reaction_role(
         &self,
         cx: &Context,
@@ -115,151 +228,786 @@ impl ReactionRoles {
     }
 }
 
+async fn add_role(
+    cx: &Context,
+    guild_id: GuildId,
+    user_id: UserId,
+    role_id: RoleId,
+) -> Result<(), Error> {
+    let res = cx
+        .http()
+        .add_guild_member_role(guild_id, user_id, role_id)
+        .reason("reaction role add")?
+        .exec()
+        .await;
+
+    ignore_permissions_lacking(res)
+}
+
+async fn remove_role(
+    cx: &Context,
+    guild_id: GuildId,
+    user_id: UserId,
+    role_id: RoleId,
+) -> Result<(), Error> {
+    let res = cx
+        .http()
+        .remove_guild_member_role(guild_id, user_id, role_id)
+        .reason("reaction role remove")?
+        .exec()
+        .await;
+
+    ignore_permissions_lacking(res)
+}
+
+/// Removes a user's reaction with `emoji` from the message of `reaction`.
+async fn remove_reaction(cx: &Context, reaction: &Reaction, emoji: &Emoji) -> Result<(), Error> {
+    let emoji = match emoji {
+        Emoji::Unicode(name) => RequestReactionType::Unicode { name },
+        Emoji::Custom(id) => RequestReactionType::Custom {
+            id: EmojiId(*id),
+            name: None,
+        },
+        // we don't know what this is exactly
+        Emoji::Legacy(_) => return Ok(()),
+    };
+
+    let res = cx
+        .http()
+        .delete_reaction(
+            reaction.channel_id,
+            reaction.message_id,
+            &emoji,
+            reaction.user_id,
+        )
+        .exec()
+        .await;
+
+    ignore_permissions_lacking(res)
+}
+
 impl_service! {
     impl Service for ReactionRoles {
         async fn handle(&self, cx: &Context, ev: &Event) -> Result<(), Error> {
             match ev {
                 Event::ReactionAdd(reaction) => self.reaction_add(cx, reaction).await,
                 Event::ReactionRemove(reaction) => self.reaction_remove(cx, reaction).await,
-                _ => Ok(()),
+                ev => self.clean_up(cx, ev).await,
             }
         }
     }
 }
 
+/// The definition of `/reactionroles`.
+pub fn spec() -> CommandSpec {
+    CommandSpec::new("reactionroles", "configure reaction roles")
+        .default_permission(false)
+        .subcommand::<AddOptions>("add", "creates a new reaction role", CreateReactionRole)
+        .subcommand::<()>(
+            "list",
+            "lists every reaction role in the server",
+            ListReactionRoles,
+        )
+        .subcommand::<RemoveOptions>(
+            "remove",
+            "removes a reaction role from a message",
+            RemoveReactionRole,
+        )
+        .subcommand::<ClearOptions>(
+            "clear",
+            "removes every reaction role from a message",
+            ClearReactionRoles,
+        )
+        .subcommand::<ModeOptions>(
+            "mode",
+            "changes what reacting to a reaction role does",
+            SetReactionRoleMode,
+        )
+        .subcommand::<CheckOptions>(
+            "check",
+            "finds reaction roles that no longer work",
+            CheckReactionRoles,
+        )
+}
+
+options! {
+    /// Options for `/reactionroles add`.
+    pub struct AddOptions {
+        /// the role to set the reaction role as
+        role: RoleId,
+        /// what reacting does; defaults to giving the role until unreacting
+        mode: Option<ModeChoice>,
+    }
+}
+
+choices! {
+    /// A [`Mode`] picked in a command.
+    pub enum ModeChoice {
+        /// normal: give the role until unreacting
+        Normal = "normal",
+        /// unique: only keep one unique role on the message
+        Unique = "unique",
+        /// verify: give the role for good
+        Verify = "verify",
+        /// drop: take the role away
+        Drop = "drop",
+        /// toggle: give or take the role away with each reaction
+        Toggle = "toggle",
+    }
+}
+
+impl From<Mode> for ModeChoice {
+    fn from(mode: Mode) -> ModeChoice {
+        match mode {
+            Mode::Normal => ModeChoice::Normal,
+            Mode::Unique => ModeChoice::Unique,
+            Mode::Verify => ModeChoice::Verify,
+            Mode::Drop => ModeChoice::Drop,
+            Mode::Toggle => ModeChoice::Toggle,
+        }
+    }
+}
+
+impl From<ModeChoice> for Mode {
+    fn from(choice: ModeChoice) -> Mode {
+        match choice {
+            ModeChoice::Normal => Mode::Normal,
+            ModeChoice::Unique => Mode::Unique,
+            ModeChoice::Verify => Mode::Verify,
+            ModeChoice::Drop => Mode::Drop,
+            ModeChoice::Toggle => Mode::Toggle,
+        }
+    }
+}
+
+/// Describes what reacting does in a mode, to finish "anyone who reacts with
+/// {emoji} to that message ...".
+fn describe(mode: Mode, role_id: RoleId) -> String {
+    let role = role_id.mention();
+
+    match mode {
+        Mode::Normal => format!("will get {} until they unreact", role),
+        Mode::Unique => format!(
+            "will get {} instead of the other unique roles on that message",
+            role
+        ),
+        Mode::Verify => format!("will get {} for good", role),
+        Mode::Drop => format!("will lose {}", role),
+        Mode::Toggle => format!("will get {}, or lose it if they already have it", role),
+    }
+}
+
 /// Allows easy creation of reaction roles.
 #[derive(Default, Clone)]
 pub struct CreateReactionRole;
 
-impl CreateReactionRole {
-    async fn command(&self, cx: &Context, command: Arguments<'_>) -> Result<(), Error> {
-        let guild_id = match command.guild_id() {
-            Some(guild_id) => guild_id,
-            None => return Err(anyhow!("guild_id is missing")),
-        };
+impl_command! {
+    impl Command for CreateReactionRole {
+        async fn command(&self, cx: &Context, command: Arguments<'_>) -> Result<(), Error> {
+            let guild_id = match command.guild_id() {
+                Some(guild_id) => guild_id,
+                None => return Err(anyhow!("guild_id is missing")),
+            };
+
+            let user_id = command.user_id();
+
+            let options = AddOptions::parse(&command)?;
+            let role_id = options.role;
+            let mode = options.mode.map(Mode::from).unwrap_or_default();
+
+            // create a response
+            command
+                .respond()
+                .content(
+                    "react with the emoji of your choice to the message of your \
+                     choice to set up the reaction role!. ⚠️ this will expire in \
+                     a minute!",
+                )
+                .ephemeral()
+                .exec(cx.http())
+                .await?;
+
+            // wait for a reaction...
+            let reaction = cx.wait_for(guild_id, move |event: &Event| match event {
+                Event::ReactionAdd(reaction) => reaction.0.user_id == user_id,
+                _ => false,
+            });
+
+            // ...or the timeout
+            select! {
+                biased;
+                _ = sleep(Duration::from_secs(60)) => {
+                    // send expiration message
+                    command
+                        .followup()
+                        .content("request has expired! try `/reactionroles add` again to continue")
+                        .ephemeral()
+                        .exec(cx.http())
+                        .await?;
+                }
+                event = reaction => {
+                    let reaction = match event? {
+                        Event::ReactionAdd(reaction) => reaction,
+                        _ => unreachable!(),
+                    };
+
+                    let emoji: Emoji = reaction.emoji.clone().into();
+
+                    // cool! we now have everything needed to create a rr!
+                    let message = Message::new(
+                        guild_id,
+                        reaction.message_id,
+                        reaction.channel_id,
+                    );
+
+                    let res = message.create(cx.db(), role_id, emoji.clone(), mode).await;
+
+                    match res {
+                        Ok(_) => {
+                            let content = format!(
+                                "reaction role set up!\n\
+                                 anyone who reacts with {} to that message {}!",
+                                emoji,
+                                describe(mode, role_id),
+                            );
+
+                            command
+                                .followup()
+                                .content(content)
+                                .ephemeral()
+                                .exec(cx.http())
+                                .await?;
+                        }
+                        Err(err) if err.exists() => {
+                            // get the existing reaction role
+                            let rr = ReactionRole::get(cx.db(), reaction.message_id, emoji.clone())
+                                .await?
+                                .expect("db told us a RR already exists, but we can't find it!");
+
+                            let content = format!(
+                                "a reaction role that gives {} has already been \
+                                 set up for the emoji {}! try removing it first \
+                                 with `/reactionroles remove`!",
+                                rr.role_id().mention(),
+                                emoji,
+                            );
+
+                            command
+                                .followup()
+                                .content(content)
+                                .ephemeral()
+                                .exec(cx.http())
+                                .await?;
+                        }
+                        Err(err) => return Err(err.into())
+                    }
+                }
+            }
 
-        let user_id = command.user_id();
-
-        let role_id = command
-            .get_string("role")?
-            .ok_or(anyhow!("role is missing for /reactionroles add!"))?
-            .parse::<u64>()
-            .map(RoleId)?;
-
-        // create a response
-        command
-            .respond()
-            .content(
-                "react with the emoji of your choice to the message of your \
-                 choice to set up the reaction role!. ⚠️ this will expire in \
-                 a minute!",
-            )
-            .ephemeral()
-            .exec(cx.http())
-            .await?;
+            Ok(())
+        }
+    }
+}
 
-        // wait for a reaction...
-        let reaction = cx.wait_for(guild_id, move |event: &Event| match event {
-            Event::ReactionAdd(reaction) => reaction.0.user_id == user_id,
-            _ => false,
-        });
+options! {
+    /// Options for `/reactionroles remove`.
+    pub struct RemoveOptions {
+        /// a link to the message, or its id
+        message: String,
+        /// the emoji of the reaction role; leave empty to pick from a list
+        emoji: Option<String>,
+    }
+}
+
+options! {
+    /// Options for `/reactionroles clear`.
+    pub struct ClearOptions {
+        /// a link to the message, or its id
+        message: String,
+    }
+}
+
+/// Gets a message id from a message link or a plain id.
+fn parse_message_id(message: &str) -> Option<MessageId> {
+    // the id is the last part of a link
+    let id = message.trim().rsplit('/').next()?;
+
+    id.parse().ok().map(MessageId)
+}
+
+/// Creates a link that jumps to a reaction role's message.
+fn jump_link(rr: &ReactionRole) -> String {
+    format!(
+        "https://discord.com/channels/{}/{}/{}",
+        rr.guild_id(),
+        rr.channel_id(),
+        rr.message_id(),
+    )
+}
+
+/// The `/reactionroles list` command.
+#[derive(Default, Clone)]
+pub struct ListReactionRoles;
+
+impl ListReactionRoles {
+    /// How long the list can get before the rest is left out.
+    const MAX_LENGTH: usize = 1800;
+}
+
+impl_command! {
+    impl Command for ListReactionRoles {
+        async fn command(&self, cx: &Context, command: Arguments<'_>) -> Result<(), Error> {
+            let guild_id = command.guild_id().ok_or(anyhow!("guild_id is missing"))?;
+
+            let rrs = Guild::new(guild_id).reaction_roles(cx.db()).await?;
+
+            let content = if rrs.is_empty() {
+                String::from("there aren't any reaction roles! set one up with `/reactionroles add`")
+            } else {
+                let mut content = String::from("reaction roles in this server:");
+                let mut last_message_id = None;
+
+                for (i, rr) in rrs.iter().enumerate() {
+                    if content.len() > Self::MAX_LENGTH {
+                        write!(content, "\n…and {} more", rrs.len() - i).unwrap();
+                        break;
+                    }
+
+                    // rows are grouped by message
+                    if last_message_id != Some(rr.message_id()) {
+                        write!(
+                            content,
+                            "\n\n**[message]({})** in {}",
+                            jump_link(rr),
+                            rr.channel_id().mention(),
+                        )
+                        .unwrap();
+
+                        last_message_id = Some(rr.message_id());
+                    }
+
+                    write!(content, "\n• {} → {}", rr.emoji(), rr.role_id().mention()).unwrap();
+
+                    if rr.mode() != Mode::Normal {
+                        write!(content, " ({})", ModeChoice::from(rr.mode()).value()).unwrap();
+                    }
+                }
+
+                content
+            };
+
+            command
+                .respond()
+                .content(content)
+                .ephemeral()
+                .exec(cx.http())
+                .await
+        }
+    }
+}
+
+state! {
+    /// The state of the `/reactionroles remove` select menu.
+    pub struct RemoveState {
+        /// The message to remove a reaction role from.
+        message_id: MessageId,
+    }
+}
+
+/// The `/reactionroles remove` command.
+#[derive(Default, Clone)]
+pub struct RemoveReactionRole;
+
+impl RemoveReactionRole {
+    /// The most options a select menu can have.
+    const MAX_OPTIONS: usize = 25;
+}
 
-        // ...or the timeout
-        select! {
-            biased;
-            _ = sleep(Duration::from_secs(60)) => {
-                // send expiration message
-                command
-                    .followup()
-                    .content("request has expired! try `/reactionroles add` again to continue")
+impl_command! {
+    impl Command for RemoveReactionRole {
+        async fn command(&self, cx: &Context, command: Arguments<'_>) -> Result<(), Error> {
+            let guild_id = command.guild_id().ok_or(anyhow!("guild_id is missing"))?;
+
+            let options = RemoveOptions::parse(&command)?;
+
+            let message_id = match parse_message_id(&options.message) {
+                Some(message_id) => message_id,
+                None => {
+                    return command
+                        .respond()
+                        .content("that isn't a message link or id!")
+                        .ephemeral()
+                        .exec(cx.http())
+                        .await;
+                }
+            };
+
+            let guild = Guild::new(guild_id);
+
+            // remove it right away if we know the emoji
+            if let Some(emoji) = options.emoji {
+                let content = match parse_emoji(&emoji) {
+                    Some(emoji) if guild.remove(cx.db(), message_id, &emoji).await? => {
+                        format!("the reaction role for {} was removed!", emoji)
+                    }
+                    Some(emoji) => format!("there isn't a reaction role for {} on that message!", emoji),
+                    None => String::from("that isn't an emoji!"),
+                };
+
+                return command
+                    .respond()
+                    .content(content)
                     .ephemeral()
                     .exec(cx.http())
-                    .await?;
+                    .await;
             }
-            event = reaction => {
-                let reaction = match event? {
-                    Event::ReactionAdd(reaction) => reaction,
-                    _ => unreachable!(),
-                };
 
-                let emoji = reaction.emoji.clone().into();
+            // otherwise, let them pick
+            let rrs = guild.by_message(cx.db(), message_id).await?;
 
-                // cool! we now have everything needed to create a rr!
-                let message = Message::new(
-                    guild_id,
-                    reaction.message_id,
-                    reaction.channel_id,
-                );
+            if rrs.is_empty() {
+                return command
+                    .respond()
+                    .content("there aren't any reaction roles on that message!")
+                    .ephemeral()
+                    .exec(cx.http())
+                    .await;
+            }
 
-                let res = message.create(cx.db(), role_id, emoji).await;
+            let names = role_names(cx, guild_id).await?;
+
+            let options = rrs
+                .iter()
+                .take(Self::MAX_OPTIONS)
+                .map(|rr| {
+                    let label = names
+                        .get(&rr.role_id())
+                        .cloned()
+                        .unwrap_or_else(|| rr.role_id().to_string());
+
+                    let mut option = select_option(label, rr.emoji().to_text());
+                    option.emoji = reaction_type(rr.emoji());
+
+                    option
+                })
+                .collect();
+
+            let mut menu = select_menu(
+                custom_id("rrremove", &RemoveState { message_id }),
+                options,
+            );
+            menu.placeholder = Some(String::from("pick a reaction role to remove"));
+
+            command
+                .respond()
+                .content("which reaction role should be removed?")
+                .action_row([TwilightComponent::SelectMenu(menu)])
+                .ephemeral()
+                .exec(cx.http())
+                .await
+        }
+    }
+}
 
-                match res {
-                    Ok(_) => {
-                        let content = format!(
-                            "reaction role set up!\n\
-                             i will now give the {} role to anyone who reacts \
-                             with {} to that message!",
-                            role_id.mention(),
-                            emoji,
-                        );
+/// Gets the names of every role in a guild.
+async fn role_names(cx: &Context, guild_id: GuildId) -> Result<HashMap<RoleId, String>, Error> {
+    let roles = cx.http().roles(guild_id).exec().await?.model().await?;
 
-                        command
-                            .followup()
-                            .content(content)
-                            .ephemeral()
-                            .exec(cx.http())
-                            .await?;
-                    }
-                    Err(err) if err.exists() => {
-                        // get the existing reaction role
-                        let rr = ReactionRole::get(cx.db(), reaction.message_id, emoji)
-                            .await?
-                            .expect("db told us a RR already exists, but we can't find it!");
+    Ok(roles.into_iter().map(|role| (role.id, role.name)).collect())
+}
 
-                        let content = format!(
-                            "a reaction role that gives {} has already been \
-                             set up for the emoji {}! try removing it first!",
-                            rr.role_id().mention(),
-                            emoji,
-                        );
+/// Handles the `/reactionroles remove` select menu.
+#[derive(Default, Clone)]
+pub struct RemoveReactionRoleMenu;
+
+impl_component! {
+    impl Component for RemoveReactionRoleMenu {
+        async fn component(
+            &self,
+            cx: &Context,
+            component: ComponentArguments<'_>,
+        ) -> Result<(), Error> {
+            let guild_id = component.guild_id().ok_or(anyhow!("guild_id is missing"))?;
+
+            let message_id = component.state::<RemoveState>()?.message_id;
+
+            let emoji = component
+                .values()
+                .first()
+                .and_then(|value| Emoji::from_text(value))
+                .ok_or(anyhow!("no reaction role was picked"))?;
+
+            let content = if Guild::new(guild_id)
+                .remove(cx.db(), message_id, &emoji)
+                .await?
+            {
+                format!("the reaction role for {} was removed!", emoji)
+            } else {
+                String::from("that reaction role was already removed!")
+            };
+
+            component
+                .update()
+                .content(content)
+                .clear_components()
+                .exec(cx.http())
+                .await
+        }
+    }
+}
 
-                        command
-                            .followup()
-                            .content(content)
-                            .ephemeral()
-                            .exec(cx.http())
-                            .await?;
+/// The `/reactionroles clear` command.
+#[derive(Default, Clone)]
+pub struct ClearReactionRoles;
+
+impl_command! {
+    impl Command for ClearReactionRoles {
+        async fn command(&self, cx: &Context, command: Arguments<'_>) -> Result<(), Error> {
+            let guild_id = command.guild_id().ok_or(anyhow!("guild_id is missing"))?;
+
+            let message = ClearOptions::parse(&command)?.message;
+
+            let content = match parse_message_id(&message) {
+                Some(message_id) => match Guild::new(guild_id).clear(cx.db(), message_id).await? {
+                    0 => String::from("there aren't any reaction roles on that message!"),
+                    1 => String::from("removed 1 reaction role from that message!"),
+                    n => format!("removed {} reaction roles from that message!", n),
+                },
+                None => String::from("that isn't a message link or id!"),
+            };
+
+            command
+                .respond()
+                .content(content)
+                .ephemeral()
+                .exec(cx.http())
+                .await
+        }
+    }
+}
+
+options! {
+    /// Options for `/reactionroles mode`.
+    pub struct ModeOptions {
+        /// a link to the message, or its id
+        message: String,
+        /// what reacting should do
+        mode: ModeChoice,
+        /// the emoji of the reaction role; leave empty to change every one on the message
+        emoji: Option<String>,
+    }
+}
+
+/// The `/reactionroles mode` command.
+#[derive(Default, Clone)]
+pub struct SetReactionRoleMode;
+
+impl_command! {
+    impl Command for SetReactionRoleMode {
+        async fn command(&self, cx: &Context, command: Arguments<'_>) -> Result<(), Error> {
+            let guild_id = command.guild_id().ok_or(anyhow!("guild_id is missing"))?;
+
+            let options = ModeOptions::parse(&command)?;
+            let mode = Mode::from(options.mode);
+
+            let message_id = parse_message_id(&options.message);
+            let emoji = options.emoji.as_deref().map(parse_emoji);
+
+            let content = match (message_id, emoji) {
+                (None, _) => String::from("that isn't a message link or id!"),
+                (_, Some(None)) => String::from("that isn't an emoji!"),
+                (Some(message_id), Some(Some(emoji))) => {
+                    let changed = Guild::new(guild_id)
+                        .set_mode(cx.db(), message_id, Some(&emoji), mode)
+                        .await?;
+
+                    if changed > 0 {
+                        format!(
+                            "the reaction role for {} is now {}!",
+                            emoji,
+                            options.mode.value()
+                        )
+                    } else {
+                        format!("there isn't a reaction role for {} on that message!", emoji)
                     }
-                    Err(err) => return Err(err.into())
                 }
-            }
+                (Some(message_id), None) => {
+                    let changed = Guild::new(guild_id)
+                        .set_mode(cx.db(), message_id, None, mode)
+                        .await?;
+
+                    match changed {
+                        0 => String::from("there aren't any reaction roles on that message!"),
+                        1 => format!(
+                            "the reaction role on that message is now {}!",
+                            options.mode.value()
+                        ),
+                        n => format!(
+                            "{} reaction roles on that message are now {}!",
+                            n,
+                            options.mode.value()
+                        ),
+                    }
+                }
+            };
+
+            command
+                .respond()
+                .content(content)
+                .ephemeral()
+                .exec(cx.http())
+                .await
         }
+    }
+}
 
-        Ok(())
+options! {
+    /// Options for `/reactionroles check`.
+    pub struct CheckOptions {
+        /// whether to remove the broken reaction roles; defaults to just listing them
+        clean: Option<bool>,
     }
 }
 
-impl_service! {
-    impl Service for CreateReactionRole {
-        async fn handle(&self, cx: &Context, ev: &Event) -> Result<(), Error> {
-            match ev {
-                Event::InteractionCreate(int) => match &int.0 {
-                    Interaction::ApplicationCommand(cmd) => {
-                        let args = Arguments::new(&*cmd);
-
-                        if args.name() == "reactionroles" {
-                            match args.get_subcommand("add")? {
-                                Some(args) => return self.command(cx, args).await,
-                                None => (),
-                            }
-                        }
+/// Why a reaction role no longer works.
+#[derive(Clone, Copy)]
+enum Broken {
+    /// The message or its channel was deleted.
+    Message,
+    /// The bot can't see the message.
+    Hidden,
+    /// The role was deleted.
+    Role,
+}
+
+impl Broken {
+    fn describe(&self) -> &'static str {
+        match self {
+            Broken::Message => "the message was deleted",
+            Broken::Hidden => "i can't see the message",
+            Broken::Role => "the role was deleted",
+        }
+    }
+}
+
+/// The `/reactionroles check` command.
+#[derive(Default, Clone)]
+pub struct CheckReactionRoles;
+
+impl CheckReactionRoles {
+    /// How long the report can get before the rest is left out.
+    const MAX_LENGTH: usize = 1800;
+}
+
+impl_command! {
+    impl Command for CheckReactionRoles {
+        async fn command(&self, cx: &Context, command: Arguments<'_>) -> Result<(), Error> {
+            let guild_id = command.guild_id().ok_or(anyhow!("guild_id is missing"))?;
+
+            let clean = CheckOptions::parse(&command)?.clean.unwrap_or(false);
+
+            // this fetches every message with reaction roles
+            let _defer = command.defer_after(cx.http(), Duration::from_secs(2), true);
+
+            let guild = Guild::new(guild_id);
+            let rrs = guild.reaction_roles(cx.db()).await?;
+
+            let names = role_names(cx, guild_id).await?;
+
+            // messages are only fetched once
+            let mut messages = HashMap::new();
+            let mut broken = Vec::new();
+
+            for rr in &rrs {
+                let message = match messages.get(&rr.message_id()) {
+                    Some(message) => *message,
+                    None => {
+                        let res = cx
+                            .http()
+                            .message(rr.channel_id(), rr.message_id())
+                            .exec()
+                            .await;
+
+                        let message = match res {
+                            Ok(_) => None,
+                            Err(err) => match error_code(&err) {
+                                Some(ErrorCode::UnknownMessage | ErrorCode::UnknownChannel) => {
+                                    Some(Broken::Message)
+                                }
+                                Some(ErrorCode::Missingaccess) => Some(Broken::Hidden),
+                                _ => return Err(err.into()),
+                            },
+                        };
+
+                        messages.insert(rr.message_id(), message);
+
+                        message
                     }
-                    _ => (),
-                },
-                _ => (),
+                };
+
+                if let Some(why) = message {
+                    broken.push((rr, why));
+                } else if !names.contains_key(&rr.role_id()) {
+                    broken.push((rr, Broken::Role));
+                }
             }
 
-            Ok(())
+            if broken.is_empty() {
+                return command
+                    .respond()
+                    .content("every reaction role works!")
+                    .ephemeral()
+                    .exec(cx.http())
+                    .await;
+            }
+
+            let mut content = String::from("these reaction roles don't work:");
+
+            for (i, (rr, why)) in broken.iter().enumerate() {
+                if content.len() > Self::MAX_LENGTH {
+                    write!(content, "\n…and {} more", broken.len() - i).unwrap();
+                    break;
+                }
+
+                write!(
+                    content,
+                    "\n• {} → {} on [this message]({}): {}",
+                    rr.emoji(),
+                    rr.role_id().mention(),
+                    jump_link(rr),
+                    why.describe(),
+                )
+                .unwrap();
+            }
+
+            if clean {
+                let mut removed = 0;
+
+                for (rr, why) in &broken {
+                    // the bot might just be missing permissions for now
+                    if matches!(why, Broken::Hidden) {
+                        continue;
+                    }
+
+                    if guild.remove(cx.db(), rr.message_id(), rr.emoji()).await? {
+                        removed += 1;
+                    }
+                }
+
+                match removed {
+                    1 => content.push_str("\n\nremoved 1 reaction role!"),
+                    n => write!(content, "\n\nremoved {} reaction roles!", n).unwrap(),
+                }
+            } else {
+                content.push_str("\n\nuse `/reactionroles check clean: true` to remove them");
+            }
+
+            command
+                .respond()
+                .content(content)
+                .ephemeral()
+                .exec(cx.http())
+                .await
         }
     }
 }
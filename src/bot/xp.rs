@@ -1,48 +1,114 @@
 //! Diminishing "experience" tracking services.
 
-use crate::command::chat::Arguments;
-use crate::impl_service;
-use crate::model::xp::{Guild, Record};
-use crate::service::{Context, Error, Service};
+use super::roles::level::reward;
 
+use crate::command::chat::Arguments;
+use crate::command::component::{button, custom_id, Arguments as ComponentArguments};
+use crate::command::spec::{CommandSpec, Options};
+use crate::model::announcement::{self, Target};
+use crate::model::decay;
+use crate::model::filter::{self, Filters};
+use crate::model::level::{self, Curve};
+use crate::model::multiplier::{self, multiplier, Multiplier};
+use crate::model::xp::{self, Award, Awarded, Gained, Guild, Record};
+use crate::service::{Context, Error};
+use crate::{choices, impl_command, impl_component, impl_service, options, state};
+
+use std::collections::{hash_map, HashMap, VecDeque};
 use std::fmt::Write;
 use std::sync::Arc;
-use std::time::{Duration, Instant};
+use std::time::{Duration, Instant, SystemTime};
 
-use dashmap::DashMap;
+use dashmap::{mapref::entry::Entry, DashMap};
 
-use twilight_model::application::interaction::Interaction;
-use twilight_model::channel::message::Message;
+use tokio::select;
+use tokio::sync::oneshot;
+use tokio::task::JoinHandle;
+use tokio::time::sleep;
+
+use twilight_model::application::component::{button::ButtonStyle, Component as TwilightComponent};
+use twilight_model::channel::message::{AllowedMentions, Message};
+use twilight_model::channel::{Channel, GuildChannel};
 use twilight_model::gateway::event::Event;
-use twilight_model::id::{GuildId, UserId};
+use twilight_model::id::{ChannelId, GuildId, RoleId, UserId};
 
 use anyhow::anyhow;
 
+type Recent = VecDeque<(Instant, String)>;
+
 /// Experience awarding service.
+///
+/// Users are awarded one KR for every second since their last award, up to
+/// [`Xp::MAX_EXP`]. Awards are kept in memory and saved in batches by
+/// [`Xp::flush`], which should be run every [`Xp::FLUSH_INTERVAL`] with
+/// [`Xp::spawn_flusher`], and once more on shutdown. If the bot crashes, at
+/// most that long of awards are lost.
+///
+/// Experience is scaled by the [`Multipliers`] of the guild, and messages
+/// caught by the guild's [`SpamFilters`] aren't awarded. Guilds can also
+/// have inactive users lose experience over time, which is done by
+/// [`Xp::spawn_decayer`].
 #[derive(Default, Clone)]
-pub struct Xp(Arc<DashMap<(GuildId, UserId), Instant>>);
+pub struct Xp {
+    pending: Arc<DashMap<(GuildId, UserId), Pending>>,
+    multipliers: Multipliers,
+    filters: SpamFilters,
+    /// The [`normalize`](filter::normalize)d recent messages of every user,
+    /// for finding duplicates.
+    recent: Arc<DashMap<(GuildId, UserId), Recent>>,
+    /// The category of every channel the bot can see.
+    categories: Arc<DashMap<ChannelId, ChannelId>>,
+}
 
 impl Xp {
     /// Maximum experience a user can be awarded at once.
     pub const MAX_EXP: i32 = 15;
 
-    /// Updates a [`Cooldown`] in the cooldowns table, returning a good amount
-    /// of exp to reward.
-    pub fn cooldown_update(&self, guild_id: GuildId, user_id: UserId) -> i32 {
-        let idx = (guild_id, user_id);
-        let now = Instant::now();
-
-        // swap instants
-        match self.0.insert(idx, now) {
-            Some(old) => match now.checked_duration_since(old) {
-                Some(duration) => exp(duration),
-                // this should not happen, but just in case.
-                None => 0,
-            },
-            None => Xp::MAX_EXP,
+    /// How often awards are saved.
+    pub const FLUSH_INTERVAL: Duration = Duration::from_secs(10);
+
+    /// How many users can have unsaved awards before they are saved early.
+    pub const MAX_PENDING: usize = 4096;
+
+    /// How many recent messages of a user are checked for duplicates.
+    pub const RECENT_MESSAGES: usize = 5;
+
+    /// How long messages are checked for duplicates.
+    pub const RECENT_FOR: Duration = Duration::from_secs(10 * 60);
+
+    /// How often inactive users are checked for decay.
+    pub const DECAY_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+    /// Create a new `Xp` service.
+    pub fn new(multipliers: Multipliers, filters: SpamFilters) -> Xp {
+        Xp {
+            multipliers,
+            filters,
+            ..Default::default()
         }
     }
 
+    /// The multipliers used to scale awards.
+    pub fn multipliers(&self) -> &Multipliers {
+        &self.multipliers
+    }
+
+    /// The spam filters used to skip messages.
+    pub fn filters(&self) -> &SpamFilters {
+        &self.filters
+    }
+
+    /// Drops the awards of a user that haven't been saved yet, so they aren't
+    /// saved after the user is reset.
+    pub fn forget_user(&self, guild_id: GuildId, user_id: UserId) {
+        self.pending.remove(&(guild_id, user_id));
+    }
+
+    /// Drops the awards of every user in a guild that haven't been saved yet.
+    pub fn forget_guild(&self, guild_id: GuildId) {
+        self.pending.retain(|&(id, _), _| id != guild_id);
+    }
+
     /// Handles a message.
     pub async fn process(&self, cx: &Context, msg: &Message) -> Result<(), Error> {
         // do not track bot messages
@@ -57,26 +123,330 @@ impl Xp {
             None => return Ok(()),
         };
 
-        let user_id = msg.author.id;
+        let now = SystemTime::now();
+
+        let roles = msg
+            .member
+            .as_ref()
+            .map(|member| member.roles.clone())
+            .unwrap_or_default();
+
+        // scale the experience
+        let multiplier = multiplier(
+            &self.multipliers.get(cx, guild_id).await?,
+            msg.channel_id,
+            self.categories
+                .get(&msg.channel_id)
+                .map(|category| *category),
+            &roles,
+        );
 
-        // figure out how much exp to award to the user
-        let exp = self.cooldown_update(guild_id, user_id);
+        if multiplier <= 0. {
+            // no experience here!
+            return Ok(());
+        }
+
+        let filters = self.filters.get(cx, guild_id).await?;
+
+        if !self.allows(&filters, guild_id, msg) {
+            // spam doesn't get experience either
+            return Ok(());
+        }
+
+        let pending = Pending {
+            first_at: now,
+            last_at: now,
+            score: 0,
+            multiplier,
+            channel_id: msg.channel_id,
+            roles,
+        };
 
-        // add experience to the user
-        Guild::new(guild_id).add(cx.db(), user_id, exp).await?;
+        self.push(guild_id, msg.author.id, pending);
+
+        if self.pending.len() >= Xp::MAX_PENDING {
+            self.flush(cx).await?;
+        }
 
         Ok(())
     }
+
+    /// Checks a message against the guild's spam filters, and remembers it
+    /// for finding duplicates.
+    fn allows(&self, filters: &Filters, guild_id: GuildId, msg: &Message) -> bool {
+        if !filters.duplicates() {
+            return filters.allows(&msg.content, None);
+        }
+
+        let mut recent = self.recent.entry((guild_id, msg.author.id)).or_default();
+
+        // forget messages that are too old to matter
+        while matches!(recent.front(), Some((at, _)) if at.elapsed() >= Xp::RECENT_FOR) {
+            recent.pop_front();
+        }
+
+        let allows = filters.allows(
+            &msg.content,
+            recent.iter().map(|(_, content)| content.as_str()),
+        );
+
+        recent.push_back((Instant::now(), filter::normalize(&msg.content)));
+
+        if recent.len() > Xp::RECENT_MESSAGES {
+            recent.pop_front();
+        }
+
+        allows
+    }
+
+    /// Saves every pending award.
+    ///
+    /// If saving fails, the awards are kept to be saved later.
+    pub async fn flush(&self, cx: &Context) -> Result<(), Error> {
+        // forget users that haven't talked in a while
+        self.recent.retain(
+            |_, recent| matches!(recent.back(), Some((at, _)) if at.elapsed() < Xp::RECENT_FOR),
+        );
+
+        // take everything that is pending
+        let keys = self
+            .pending
+            .iter()
+            .map(|entry| *entry.key())
+            .collect::<Vec<_>>();
+        let pending = keys
+            .into_iter()
+            .filter_map(|key| self.pending.remove(&key))
+            .collect::<HashMap<_, _>>();
+
+        if pending.is_empty() {
+            return Ok(());
+        }
+
+        let awards = pending
+            .iter()
+            .map(|(&(guild_id, user_id), pending)| Award {
+                guild_id,
+                user_id,
+                first_at: pending.first_at,
+                last_at: pending.last_at,
+                score: pending.score,
+                multiplier: pending.multiplier,
+            })
+            .collect::<Vec<_>>();
+
+        let awarded = match xp::award(cx.db(), &awards, Xp::MAX_EXP).await {
+            Ok(awarded) => awarded,
+            Err(err) => {
+                // put everything back
+                for ((guild_id, user_id), pending) in pending {
+                    self.push(guild_id, user_id, pending);
+                }
+
+                return Err(err.into());
+            }
+        };
+
+        let mut curves = HashMap::new();
+
+        for awarded in awarded {
+            let idx = (awarded.guild_id(), awarded.user_id());
+
+            if let Some(pending) = pending.get(&idx) {
+                if let Err(err) = level_up(cx, &mut curves, &awarded, pending).await {
+                    error!("failed to level up user: {:?}", err);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Saves pending awards every [`Xp::FLUSH_INTERVAL`] in the background.
+    ///
+    /// Use [`Flusher::stop`] to save whatever is left and stop.
+    pub fn spawn_flusher(&self, cx: Context) -> Flusher {
+        let xp = self.clone();
+        let (stop, mut stopped) = oneshot::channel();
+
+        let task = tokio::spawn(async move {
+            loop {
+                // a flush is never interrupted, so nothing it took from
+                // pending is lost
+                let last = select! {
+                    _ = sleep(Xp::FLUSH_INTERVAL) => false,
+                    _ = &mut stopped => true,
+                };
+
+                if let Err(err) = xp.flush(&cx).await {
+                    error!("failed to save xp: {:?}", err);
+                }
+
+                if last {
+                    break;
+                }
+            }
+        });
+
+        Flusher { stop, task }
+    }
+
+    /// Applies the decay policy of every guild every [`Xp::DECAY_INTERVAL`]
+    /// in the background.
+    ///
+    /// See [`decay::decay`] for how experience decays.
+    pub fn spawn_decayer(cx: Context) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            loop {
+                match decay::decay(cx.db()).await {
+                    Ok(0) => (),
+                    Ok(decayed) => info!("decayed the xp of {} users", decayed),
+                    Err(err) => error!("failed to decay xp: {:?}", err),
+                }
+
+                sleep(Xp::DECAY_INTERVAL).await;
+            }
+        })
+    }
+
+    fn update_category(&self, channel: &GuildChannel) {
+        let parent_id = match channel {
+            GuildChannel::Text(channel) => channel.parent_id,
+            GuildChannel::Voice(channel) => channel.parent_id,
+            GuildChannel::Stage(channel) => channel.parent_id,
+            _ => None,
+        };
+
+        match parent_id {
+            Some(parent_id) => {
+                self.categories.insert(channel.id(), parent_id);
+            }
+            None => {
+                self.categories.remove(&channel.id());
+            }
+        }
+    }
+
+    fn push(&self, guild_id: GuildId, user_id: UserId, pending: Pending) {
+        match self.pending.entry((guild_id, user_id)) {
+            Entry::Occupied(mut entry) => entry.get_mut().merge(pending),
+            Entry::Vacant(entry) => {
+                entry.insert(pending);
+            }
+        }
+    }
 }
 
-impl_service! {
-    impl Service for Xp {
-        async fn handle(&self, cx: &Context, ev: &Event) -> Result<(), Error> {
-            match ev {
-                Event::MessageCreate(msg) => self.process(cx, msg).await,
-                _ => Ok(()),
+type CachedMultipliers = (Instant, Arc<Vec<Multiplier>>);
+
+/// A cache of the experience multipliers of every guild.
+///
+/// Multipliers are cached for [`Multipliers::TTL`], so changes made by other
+/// instances of the bot take at most that long to apply. This type is cheap
+/// to clone.
+#[derive(Default, Clone)]
+pub struct Multipliers(Arc<DashMap<GuildId, CachedMultipliers>>);
+
+impl Multipliers {
+    /// How long multipliers are cached.
+    pub const TTL: Duration = Duration::from_secs(300);
+
+    /// Gets the multipliers of a guild.
+    pub async fn get(
+        &self,
+        cx: &Context,
+        guild_id: GuildId,
+    ) -> Result<Arc<Vec<Multiplier>>, Error> {
+        if let Some(entry) = self.0.get(&guild_id) {
+            let (fetched_at, multipliers) = entry.value();
+
+            if fetched_at.elapsed() < Multipliers::TTL {
+                return Ok(multipliers.clone());
+            }
+        }
+
+        let multipliers = Arc::new(
+            multiplier::Guild::new(guild_id)
+                .multipliers(cx.db())
+                .await?,
+        );
+
+        self.0
+            .insert(guild_id, (Instant::now(), multipliers.clone()));
+
+        Ok(multipliers)
+    }
+
+    /// Forgets the multipliers of a guild, so they are fetched again.
+    pub fn invalidate(&self, guild_id: GuildId) {
+        self.0.remove(&guild_id);
+    }
+}
+
+/// A cache of the spam filters of every guild.
+///
+/// Filters are cached for [`SpamFilters::TTL`], so changes made by other
+/// instances of the bot take at most that long to apply. This type is cheap
+/// to clone.
+#[derive(Default, Clone)]
+pub struct SpamFilters(Arc<DashMap<GuildId, (Instant, Filters)>>);
+
+impl SpamFilters {
+    /// How long filters are cached.
+    pub const TTL: Duration = Duration::from_secs(300);
+
+    /// Gets the filters of a guild.
+    pub async fn get(&self, cx: &Context, guild_id: GuildId) -> Result<Filters, Error> {
+        if let Some(entry) = self.0.get(&guild_id) {
+            let (fetched_at, filters) = entry.value();
+
+            if fetched_at.elapsed() < SpamFilters::TTL {
+                return Ok(*filters);
             }
         }
+
+        let filters = filter::Guild::new(guild_id).filters(cx.db()).await?;
+
+        self.0.insert(guild_id, (Instant::now(), filters));
+
+        Ok(filters)
+    }
+
+    /// Forgets the filters of a guild, so they are fetched again.
+    pub fn invalidate(&self, guild_id: GuildId) {
+        self.0.remove(&guild_id);
+    }
+}
+
+/// Experience earned by a user that hasn't been saved yet.
+#[derive(Debug)]
+struct Pending {
+    first_at: SystemTime,
+    last_at: SystemTime,
+    /// The experience for every message but the first.
+    score: i32,
+    /// The multiplier of the first message.
+    multiplier: f64,
+    /// Where the last message was sent, for announcements.
+    channel_id: ChannelId,
+    /// The roles the user had, for level roles.
+    roles: Vec<RoleId>,
+}
+
+impl Pending {
+    /// Merges awards that came after this one.
+    fn merge(&mut self, after: Pending) {
+        let cooldown = after
+            .first_at
+            .duration_since(self.last_at)
+            .map(|duration| (exp(duration) as f64 * after.multiplier) as i32)
+            // this should not happen, but just in case.
+            .unwrap_or(0);
+
+        self.score += cooldown + after.score;
+        self.last_at = after.last_at;
+        self.channel_id = after.channel_id;
+        self.roles = after.roles;
     }
 }
 
@@ -88,6 +458,145 @@ fn exp(duration: Duration) -> i32 {
     exp.min(Xp::MAX_EXP)
 }
 
+/// Rewards a user if they leveled up.
+async fn level_up(
+    cx: &Context,
+    curves: &mut HashMap<GuildId, Curve>,
+    awarded: &Awarded,
+    pending: &Pending,
+) -> Result<(), Error> {
+    let (guild_id, user_id) = (awarded.guild_id(), awarded.user_id());
+    let (score, exp) = (awarded.score(), awarded.awarded());
+
+    if exp <= 0 {
+        return Ok(());
+    }
+
+    // only fetch the curve of each guild once
+    let curve = match curves.entry(guild_id) {
+        hash_map::Entry::Occupied(entry) => entry.into_mut(),
+        hash_map::Entry::Vacant(entry) => {
+            entry.insert(level::Guild::new(guild_id).curve(cx.db()).await?)
+        }
+    };
+
+    let level = curve.level(score);
+
+    if curve.level(score - exp) < level {
+        // a role that can't be given shouldn't stop the announcement
+        if let Err(err) = reward(cx, guild_id, user_id, &pending.roles, level).await {
+            error!("failed to give level roles: {:?}", err);
+        }
+
+        announce(cx, guild_id, user_id, pending.channel_id, level, score).await?;
+    }
+
+    Ok(())
+}
+
+/// Announces that a user leveled up.
+async fn announce(
+    cx: &Context,
+    guild_id: GuildId,
+    user_id: UserId,
+    channel_id: ChannelId,
+    level: i32,
+    score: i32,
+) -> Result<(), Error> {
+    let announcement = announcement::Guild::new(guild_id)
+        .announcement(cx.db())
+        .await?;
+
+    let channel_id = match announcement.target() {
+        Target::Same => channel_id,
+        Target::Channel(channel_id) => channel_id,
+        Target::Dm => {
+            cx.http()
+                .create_private_channel(user_id)
+                .exec()
+                .await?
+                .model()
+                .await?
+                .id
+        }
+        Target::None => return Ok(()),
+    };
+
+    let content = announcement.render(user_id, level, score);
+
+    // only ping the user that leveled up
+    let allowed_mentions = AllowedMentions {
+        users: vec![user_id],
+        ..Default::default()
+    };
+
+    cx.http()
+        .create_message(channel_id)
+        .content(&content)?
+        .allowed_mentions(allowed_mentions)
+        .exec()
+        .await?;
+
+    Ok(())
+}
+
+/// The task started by [`Xp::spawn_flusher`].
+pub struct Flusher {
+    stop: oneshot::Sender<()>,
+    task: JoinHandle<()>,
+}
+
+impl Flusher {
+    /// Saves the awards that are still pending, then stops.
+    ///
+    /// If a flush is already running, this waits for it to finish first.
+    pub async fn stop(self) {
+        // if the task is gone, there is nothing to wait for
+        let _ = self.stop.send(());
+
+        if let Err(err) = self.task.await {
+            error!("xp flusher failed: {:?}", err);
+        }
+    }
+}
+
+impl_service! {
+    impl Service for Xp {
+        async fn handle(&self, cx: &Context, ev: &Event) -> Result<(), Error> {
+            match ev {
+                Event::MessageCreate(msg) => self.process(cx, msg).await,
+                Event::GuildCreate(guild) => {
+                    for channel in &guild.channels {
+                        self.update_category(channel);
+                    }
+
+                    Ok(())
+                }
+                Event::ChannelCreate(channel) => {
+                    if let Channel::Guild(channel) = &channel.0 {
+                        self.update_category(channel);
+                    }
+
+                    Ok(())
+                }
+                Event::ChannelUpdate(channel) => {
+                    if let Channel::Guild(channel) = &channel.0 {
+                        self.update_category(channel);
+                    }
+
+                    Ok(())
+                }
+                Event::ChannelDelete(channel) => {
+                    self.categories.remove(&channel.0.id());
+
+                    Ok(())
+                }
+                _ => Ok(()),
+            }
+        }
+    }
+}
+
 /// Service that enables the `/rank` command.
 ///
 /// ```txt
@@ -97,113 +606,372 @@ fn exp(duration: Duration) -> i32 {
 #[derive(Default, Clone)]
 pub struct RankCommand;
 
+options! {
+    /// Options for `/rank`.
+    pub struct RankOptions {
+        /// the user to check
+        user: Option<UserId>,
+    }
+}
+
 impl RankCommand {
-    async fn command(&self, cx: &Context, command: Arguments<'_>) -> Result<(), Error> {
-        // get guild id
-        let guild_id = command.guild_id().ok_or(anyhow!("guild_id is missing"))?;
-
-        // get the user_id
-        let user_id = match command.get_string("user")? {
-            Some(id) => id.parse::<u64>().map(UserId)?,
-            None => command.user_id(),
-        };
+    /// The definition of `/rank`.
+    pub fn spec() -> CommandSpec {
+        CommandSpec::new(
+            "rank",
+            "returns your or another user's level and KR balance",
+        )
+        .options::<RankOptions>()
+        .handler(RankCommand)
+    }
+}
 
-        // finally.... finally... find the exp for the specified user
-        let user = Guild::new(guild_id).get(cx.db(), user_id).await?;
+impl_command! {
+    impl Command for RankCommand {
+        async fn command(&self, cx: &Context, command: Arguments<'_>) -> Result<(), Error> {
+            // get guild id
+            let guild_id = command.guild_id().ok_or(anyhow!("guild_id is missing"))?;
 
-        // create a response
-        let content = format!(
-            "user <@{}> is level {} with {}KR",
-            user_id,
-            user.level(),
-            user.score(),
-        );
+            let options = RankOptions::parse(&command)?;
 
-        command.respond().content(content).exec(cx.http()).await?;
+            // the database might take a while
+            let _defer = command.defer_after(cx.http(), Duration::from_secs(2), false);
 
-        Ok(())
-    }
-}
+            // get the user_id
+            let user_id = options.user.unwrap_or_else(|| command.user_id());
 
-impl_service! {
-    impl Service for RankCommand {
-        async fn handle(&self, cx: &Context, ev: &Event) -> Result<(), Error> {
-            match ev {
-                Event::InteractionCreate(int) => match &int.0 {
-                    Interaction::ApplicationCommand(cmd) => {
-                        let args = Arguments::new(&*cmd);
+            // finally.... finally... find the exp for the specified user
+            let guild = Guild::new(guild_id);
+            let user = guild.get(cx.db(), user_id).await?;
+            let rank = guild.rank_of(cx.db(), user_id).await?;
+            let curve = level::Guild::new(guild_id).curve(cx.db()).await?;
 
-                        if args.name() == "rank" {
-                            return self.command(cx, args).await;
-                        }
-                    }
-                    _ => (),
-                },
-                _ => (),
+            let level = user.level(&curve);
+
+            // create a response
+            let mut content = format!(
+                "user <@{}> is level {} with {}KR",
+                user_id,
+                level,
+                user.score(),
+            );
+
+            match rank {
+                Some(rank) => write!(content, "\n#{} of {}", rank.position(), rank.total()),
+                None => write!(content, "\nunranked"),
             }
+            .unwrap();
+
+            // find the progress to the next level
+            let floor = curve.score_for(level).unwrap_or(0);
+
+            match curve.score_for(level + 1) {
+                Some(ceil) => write!(
+                    content,
+                    " • {}KR to level {}\n{} {}/{}",
+                    ceil - user.score(),
+                    level + 1,
+                    progress_bar(user.score() - floor, ceil - floor),
+                    user.score() - floor,
+                    ceil - floor,
+                ),
+                None => write!(content, " • max level"),
+            }
+            .unwrap();
+
+            command.respond().content(content).exec(cx.http()).await?;
 
             Ok(())
         }
     }
 }
 
+fn progress_bar(progress: i32, total: i32) -> String {
+    const LENGTH: i32 = 10;
+
+    let filled = if total > 0 {
+        (progress.clamp(0, total) * LENGTH / total) as usize
+    } else {
+        0
+    };
+
+    let mut bar = "▰".repeat(filled);
+    bar.push_str(&"▱".repeat(LENGTH as usize - filled));
+    bar
+}
+
 /// Returns the exp leaders of a guild.
+///
+/// ```txt
+/// /top - Gets a page of the guild's leaderboard.
+///     [page] - The page to show. If omitted, defaults to the first page.
+///     [period] - Only count KR earned this week or month. If omitted,
+///                defaults to all time.
+/// ```
 #[derive(Default, Clone)]
 pub struct TopCommand;
 
 impl TopCommand {
-    async fn command(&self, cx: &Context, command: Arguments<'_>) -> Result<(), Error> {
-        // get guild id and role id
-        let guild_id = command.guild_id().ok_or(anyhow!("guild_id is missing"))?;
+    /// How many users are shown on a page.
+    pub const PAGE_SIZE: u64 = 10;
+
+    /// The definition of `/top`.
+    pub fn spec() -> CommandSpec {
+        CommandSpec::new("top", "returns the leaderboard of the guild in KR balance")
+            .options::<TopOptions>()
+            .handler(TopCommand)
+    }
+}
 
-        // get the top listing
-        let top = Guild::new(guild_id).top(cx.db(), 10, 0).await?;
+options! {
+    /// Options for `/top`.
+    pub struct TopOptions {
+        /// the page of the leaderboard to show; defaults to your page
+        page: Option<i64>,
+        /// only count KR earned in this period
+        period: Option<Period>,
+    }
+}
 
-        // create a response
-        let content = create_top_message(&top);
+choices! {
+    /// A period of time leaderboards can cover.
+    pub enum Period {
+        /// this week
+        Week = "week",
+        /// this month
+        Month = "month",
+        /// all time
+        AllTime = "all-time",
+    }
+}
 
-        command.respond().content(content).exec(cx.http()).await?;
+impl Period {
+    /// When the period started, or `None` for all time.
+    ///
+    /// Weeks and months are rolling, so they always cover the last 7 and 30
+    /// days.
+    pub fn since(&self) -> Option<SystemTime> {
+        let days = match self {
+            Period::Week => 7,
+            Period::Month => 30,
+            Period::AllTime => return None,
+        };
 
-        Ok(())
+        SystemTime::now().checked_sub(Duration::from_secs(days * 24 * 60 * 60))
+    }
+
+    fn describe(&self) -> &'static str {
+        match self {
+            Period::Week => " this week",
+            Period::Month => " this month",
+            Period::AllTime => "",
+        }
     }
 }
 
-impl_service! {
-    impl Service for TopCommand {
-        async fn handle(&self, cx: &Context, ev: &Event) -> Result<(), Error> {
-            match ev {
-                Event::InteractionCreate(int) => match &int.0 {
-                    Interaction::ApplicationCommand(cmd) => {
-                        let args = Arguments::new(&*cmd);
+state! {
+    /// The state of the `/top` navigation buttons.
+    pub struct TopState {
+        /// The page to go to, or `None` to go to the page of whoever clicked.
+        page: Option<u64>,
+        /// The period the leaderboard covers.
+        period: Period,
+    }
+}
 
-                        if args.name() == "top" {
-                            return self.command(cx, args).await;
-                        }
-                    }
-                    _ => (),
-                },
-                _ => (),
+impl_command! {
+    impl Command for TopCommand {
+        async fn command(&self, cx: &Context, command: Arguments<'_>) -> Result<(), Error> {
+            // get guild id and role id
+            let guild_id = command.guild_id().ok_or(anyhow!("guild_id is missing"))?;
+
+            let options = TopOptions::parse(&command)?;
+
+            let period = options.period.unwrap_or(Period::AllTime);
+
+            // the database might take a while
+            let _defer = command.defer_after(cx.http(), Duration::from_secs(2), false);
+
+            // pages start at 1 for users
+            let page = match options.page {
+                Some(page) => page.max(1) as u64 - 1,
+                None => page_of(cx, guild_id, command.user_id(), period).await?,
+            };
+
+            let (content, buttons) = create_top_page(cx, guild_id, page, period).await?;
+
+            command
+                .respond()
+                .content(content)
+                .action_row(buttons)
+                .exec(cx.http())
+                .await
+        }
+    }
+}
+
+/// Handles the `/top` navigation buttons.
+#[derive(Default, Clone)]
+pub struct TopButtons;
+
+impl_component! {
+    impl Component for TopButtons {
+        async fn component(
+            &self,
+            cx: &Context,
+            component: ComponentArguments<'_>,
+        ) -> Result<(), Error> {
+            let guild_id = component.guild_id().ok_or(anyhow!("guild_id is missing"))?;
+
+            let state = component.state::<TopState>()?;
+
+            let _defer = component.defer_after(cx.http(), Duration::from_secs(2));
+
+            let page = match state.page {
+                Some(page) => page,
+                None => page_of(cx, guild_id, component.user_id(), state.period).await?,
+            };
+
+            let (content, buttons) = create_top_page(cx, guild_id, page, state.period).await?;
+
+            component
+                .update()
+                .content(content)
+                .action_row(buttons)
+                .exec(cx.http())
+                .await
+        }
+    }
+}
+
+/// Finds the page of the leaderboard a user is on.
+///
+/// Users that aren't ranked get the first page.
+async fn page_of(
+    cx: &Context,
+    guild_id: GuildId,
+    user_id: UserId,
+    period: Period,
+) -> Result<u64, Error> {
+    let guild = Guild::new(guild_id);
+
+    let rank = match period.since() {
+        Some(since) => guild.rank_of_since(cx.db(), user_id, since).await?,
+        None => guild.rank_of(cx.db(), user_id).await?,
+    };
+
+    Ok(rank
+        .map(|rank| (rank.position() as u64 - 1) / TopCommand::PAGE_SIZE)
+        .unwrap_or(0))
+}
+
+async fn create_top_page(
+    cx: &Context,
+    guild_id: GuildId,
+    page: u64,
+    period: Period,
+) -> Result<(String, Vec<TwilightComponent>), Error> {
+    let guild = Guild::new(guild_id);
+    let since = period.since();
+
+    let total = match since {
+        Some(since) => guild.count_since(cx.db(), since).await?,
+        None => guild.count(cx.db()).await?,
+    } as u64;
+    let pages = total.div_ceil(TopCommand::PAGE_SIZE).max(1);
+    let page = page.min(pages - 1);
+
+    // get the top listing and create a response
+    let offset = (page * TopCommand::PAGE_SIZE) as usize;
+    let mut content = match since {
+        Some(since) => {
+            let top = guild
+                .top_since(cx.db(), since, TopCommand::PAGE_SIZE, page)
+                .await?;
+
+            // levels don't mean much for part of a user's KR
+            if top.is_empty() {
+                String::from("NO MEMBERS HAVE EARNED KR IN THIS PERIOD!!!")
+            } else {
+                create_period_message(&top, offset)
             }
+        }
+        None => {
+            let top = guild.top(cx.db(), TopCommand::PAGE_SIZE, page).await?;
+            let curve = level::Guild::new(guild_id).curve(cx.db()).await?;
 
-            Ok(())
+            create_top_message(&top, &curve, offset)
         }
+    };
+
+    if total > 0 {
+        write!(
+            content,
+            "\n\npage {} of {} • {} ranked members{}",
+            page + 1,
+            pages,
+            total,
+            period.describe(),
+        )
+        .unwrap();
     }
+
+    let mut previous = button(
+        ButtonStyle::Secondary,
+        custom_id(
+            "top",
+            &TopState {
+                page: Some(page.saturating_sub(1)),
+                period,
+            },
+        ),
+        "Previous",
+    );
+    previous.disabled = page == 0;
+
+    let me = button(
+        ButtonStyle::Primary,
+        custom_id("top", &TopState { page: None, period }),
+        "Find me",
+    );
+
+    let mut next = button(
+        ButtonStyle::Secondary,
+        custom_id(
+            "top",
+            &TopState {
+                page: Some(page + 1),
+                period,
+            },
+        ),
+        "Next",
+    );
+    next.disabled = page + 1 >= pages;
+
+    let buttons = [previous, me, next]
+        .into_iter()
+        .map(TwilightComponent::Button)
+        .collect();
+
+    Ok((content, buttons))
 }
 
-fn create_top_message(top: &[Record]) -> String {
-    if top.len() > 0 {
+fn create_top_message(top: &[Record], curve: &Curve, offset: usize) -> String {
+    if !top.is_empty() {
         let mut content = String::new();
 
-        for (i, record) in top.into_iter().enumerate() {
+        for (i, record) in top.iter().enumerate() {
             if i > 0 {
                 content.push('\n')
             }
 
             write!(
                 content,
-                "{} {}KR > <@{}> ",
-                top_emoji(i),
+                "{} #{} {}KR (lvl {}) > <@{}> ",
+                top_emoji(offset + i),
+                offset + i + 1,
                 record.score(),
+                record.level(curve),
                 record.user_id()
             )
             .unwrap();
@@ -216,6 +984,28 @@ fn create_top_message(top: &[Record]) -> String {
     }
 }
 
+fn create_period_message(top: &[Gained], offset: usize) -> String {
+    let mut content = String::new();
+
+    for (i, record) in top.iter().enumerate() {
+        if i > 0 {
+            content.push('\n')
+        }
+
+        write!(
+            content,
+            "{} #{} +{}KR > <@{}> ",
+            top_emoji(offset + i),
+            offset + i + 1,
+            record.amount(),
+            record.user_id()
+        )
+        .unwrap();
+    }
+
+    content
+}
+
 fn top_emoji(idx: usize) -> char {
     match idx {
         0 => '🥇',
@@ -1,20 +1,29 @@
 //! Types to make chat commands less of a pain in the butt.
 
 use twilight_model::application::interaction::application_command::{
-    ApplicationCommand, CommandDataOption,
+    ApplicationCommand, CommandDataOption, CommandInteractionDataResolved, InteractionChannel,
+    InteractionMember,
 };
-use twilight_model::id::{GuildId, InteractionId, UserId};
+use twilight_model::guild::Role;
+use twilight_model::id::{GuildId, InteractionId, MessageId, UserId};
+use twilight_model::user::User;
 
-use super::{Response, ResponseType};
+use twilight_http::Client;
+
+use super::{defer_after, DeferGuard, Response, ResponseState, ResponseType};
 
-use std::error::Error;
 use std::fmt::{self, Display, Formatter};
 use std::num::ParseIntError;
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::Error;
 
 /// An easy way to index into a chat input interaction's arguments.
 pub struct Arguments<'a> {
     top: &'a ApplicationCommand,
     options: &'a [CommandDataOption],
+    state: Arc<ResponseState>,
 }
 
 impl<'a> Arguments<'a> {
@@ -23,6 +32,7 @@ impl<'a> Arguments<'a> {
         Arguments {
             top,
             options: &top.data.options,
+            state: Arc::default(),
         }
     }
 
@@ -67,12 +77,39 @@ impl<'a> Arguments<'a> {
                 CommandDataOption::SubCommand { options, .. } => Ok(Arguments {
                     top: self.top,
                     options,
+                    state: self.state.clone(),
                 }),
                 opt => Err(ArgError::InvalidType(opt.kind())),
             })
             .transpose()
     }
 
+    /// Gets the subcommand that was invoked, if there is one.
+    ///
+    /// Unlike [`Arguments::get_subcommand`], this doesn't need to know the name
+    /// of the subcommand ahead of time.
+    pub fn subcommand(&self) -> Option<(&'a str, Arguments<'a>)> {
+        self.options.iter().find_map(|option| match option {
+            CommandDataOption::SubCommand { name, options } => Some((
+                name.as_str(),
+                Arguments {
+                    top: self.top,
+                    options,
+                    state: self.state.clone(),
+                },
+            )),
+            _ => None,
+        })
+    }
+
+    /// Gets a subcommand group, if it exists.
+    ///
+    /// Subcommand groups are indexed exactly like subcommands, so the returned
+    /// `Arguments` can be used with [`Arguments::get_subcommand`].
+    pub fn get_subcommand_group(&self, name: &str) -> Result<Option<Arguments<'a>>, ArgError> {
+        self.get_subcommand(name)
+    }
+
     /// Gets a string argument.
     pub fn get_string(&self, name: &str) -> Result<Option<&'a str>, ArgError> {
         self.get(name)
@@ -83,25 +120,192 @@ impl<'a> Arguments<'a> {
             .transpose()
     }
 
+    /// Gets an integer argument.
+    pub fn get_integer(&self, name: &str) -> Result<Option<i64>, ArgError> {
+        self.get(name)
+            .map(|s| match s {
+                CommandDataOption::Integer { value, .. } => Ok(*value),
+                opt => Err(ArgError::InvalidType(opt.kind())),
+            })
+            .transpose()
+    }
+
+    /// Gets a boolean argument.
+    pub fn get_boolean(&self, name: &str) -> Result<Option<bool>, ArgError> {
+        self.get(name)
+            .map(|s| match s {
+                CommandDataOption::Boolean { value, .. } => Ok(*value),
+                opt => Err(ArgError::InvalidType(opt.kind())),
+            })
+            .transpose()
+    }
+
+    /// Gets a user argument.
+    pub fn get_user(&self, name: &str) -> Result<Option<&'a User>, ArgError> {
+        self.get_id(name)?
+            .map(|id| {
+                self.resolved()
+                    .and_then(|r| r.users.iter().find(|user| user.id.0 == id))
+                    .ok_or(ArgError::Unresolved(id))
+            })
+            .transpose()
+    }
+
+    /// Gets the guild member of a user argument.
+    ///
+    /// Returns `Ok(None)` if the argument is missing, or if the user isn't a
+    /// member of the guild.
+    pub fn get_member(&self, name: &str) -> Result<Option<&'a InteractionMember>, ArgError> {
+        Ok(self.get_id(name)?.and_then(|id| {
+            self.resolved()
+                .and_then(|r| r.members.iter().find(|member| member.id.0 == id))
+        }))
+    }
+
+    /// Gets a role argument.
+    pub fn get_role(&self, name: &str) -> Result<Option<&'a Role>, ArgError> {
+        self.get_id(name)?
+            .map(|id| {
+                self.resolved()
+                    .and_then(|r| r.roles.iter().find(|role| role.id.0 == id))
+                    .ok_or(ArgError::Unresolved(id))
+            })
+            .transpose()
+    }
+
+    /// Gets a channel argument.
+    pub fn get_channel(&self, name: &str) -> Result<Option<&'a InteractionChannel>, ArgError> {
+        self.get_id(name)?
+            .map(|id| {
+                self.resolved()
+                    .and_then(|r| r.channels.iter().find(|channel| channel.id.0 == id))
+                    .ok_or(ArgError::Unresolved(id))
+            })
+            .transpose()
+    }
+
+    /// Gets a mentionable argument, which is either a user or a role.
+    pub fn get_mentionable(&self, name: &str) -> Result<Option<Mentionable<'a>>, ArgError> {
+        self.get_id(name)?
+            .map(|id| {
+                let resolved = self.resolved();
+
+                resolved
+                    .and_then(|r| r.users.iter().find(|user| user.id.0 == id))
+                    .map(Mentionable::User)
+                    .or_else(|| {
+                        resolved
+                            .and_then(|r| r.roles.iter().find(|role| role.id.0 == id))
+                            .map(Mentionable::Role)
+                    })
+                    .ok_or(ArgError::Unresolved(id))
+            })
+            .transpose()
+    }
+
     /// Starts building a [`Response`].
-    pub fn respond(&self) -> Response {
-        Response::new(self.top.id, &self.top.token, ResponseType::Initial)
+    ///
+    /// If the interaction was deferred, this edits the original response.
+    pub fn respond(&self) -> Response<'_> {
+        self.response(ResponseType::Initial)
     }
 
     /// Starts building a [`Response`] for a followup.
-    pub fn followup(&self) -> Response {
-        Response::new(self.top.id, &self.top.token, ResponseType::Followup)
+    pub fn followup(&self) -> Response<'_> {
+        self.response(ResponseType::Followup)
+    }
+
+    /// Starts building a deferred "thinking..." [`Response`].
+    ///
+    /// Responses made with [`Arguments::respond`] after this edit the
+    /// original response.
+    pub fn defer(&self) -> Response<'_> {
+        self.response(ResponseType::Deferred)
+    }
+
+    /// Starts building an edit of the original response.
+    pub fn edit(&self) -> Response<'_> {
+        self.response(ResponseType::EditOriginal)
+    }
+
+    /// Starts building an edit of a followup message.
+    pub fn edit_followup(&self, message_id: MessageId) -> Response<'_> {
+        self.response(ResponseType::EditFollowup(message_id))
+    }
+
+    /// Deletes the original response.
+    pub async fn delete_original(&self, client: &Client) -> Result<(), Error> {
+        client
+            .delete_interaction_original(&self.top.token)?
+            .exec()
+            .await
+            .map(|_| ())
+            .map_err(From::from)
+    }
+
+    /// Deletes a followup message.
+    pub async fn delete_followup(
+        &self,
+        client: &Client,
+        message_id: MessageId,
+    ) -> Result<(), Error> {
+        client
+            .delete_followup_message(&self.top.token, message_id)?
+            .exec()
+            .await
+            .map(|_| ())
+            .map_err(From::from)
+    }
+
+    /// Defers the interaction if it hasn't been responded to after `after`.
+    ///
+    /// Hold on to the returned guard until the handler is finished.
+    #[must_use = "the deferral is cancelled when the guard is dropped"]
+    pub fn defer_after(&self, client: &Client, after: Duration, ephemeral: bool) -> DeferGuard {
+        defer_after(
+            client,
+            self.top.id,
+            &self.top.token,
+            self.state.clone(),
+            after,
+            ResponseType::Deferred,
+            ephemeral,
+        )
+    }
+
+    fn response(&self, ty: ResponseType) -> Response<'_> {
+        Response::new(self.top.id, &self.top.token, ty).with_state(self.state.clone())
     }
 
     fn get(&self, name: &str) -> Option<&'a CommandDataOption> {
         self.options.iter().find(|option| option.name() == name)
     }
+
+    /// Gets the raw id of a user, role, channel or mentionable argument.
+    fn get_id(&self, name: &str) -> Result<Option<u64>, ArgError> {
+        self.get_string(name)?
+            .map(|id| id.parse::<u64>().map_err(From::from))
+            .transpose()
+    }
+
+    fn resolved(&self) -> Option<&'a CommandInteractionDataResolved> {
+        self.top.data.resolved.as_ref()
+    }
+}
+
+/// A resolved mentionable argument.
+#[derive(Clone, Copy, Debug)]
+pub enum Mentionable<'a> {
+    User(&'a User),
+    Role(&'a Role),
 }
 
 /// An error returned by any of the `Arguments::get_*` functions.
 #[derive(Debug)]
 pub enum ArgError {
+    InvalidChoice(String),
     InvalidType(&'static str),
+    Missing(&'static str),
     ParseInt(ParseIntError),
     Unresolved(u64),
 }
@@ -109,15 +313,17 @@ pub enum ArgError {
 impl Display for ArgError {
     fn fmt(&self, f: &mut Formatter) -> fmt::Result {
         match self {
+            ArgError::InvalidChoice(value) => write!(f, "unexpected choice {:?}", value),
             ArgError::InvalidType(ty) => write!(f, "unexpected type {}", ty),
+            ArgError::Missing(name) => write!(f, "missing required option {}", name),
             ArgError::ParseInt(err) => Display::fmt(err, f),
             ArgError::Unresolved(id) => write!(f, "unresolved id: {}", id),
         }
     }
 }
 
-impl Error for ArgError {
-    fn source(&self) -> Option<&(dyn Error + 'static)> {
+impl std::error::Error for ArgError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
         match self {
             ArgError::ParseInt(err) => Some(err),
             _ => None,
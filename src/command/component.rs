@@ -0,0 +1,611 @@
+//! Types to make message component interactions less of a pain in the butt.
+
+use twilight_model::application::component::{
+    button::{Button, ButtonStyle},
+    select_menu::{SelectMenu, SelectMenuOption},
+};
+use twilight_model::application::interaction::MessageComponentInteraction;
+use twilight_model::channel::Message;
+use twilight_model::id::{ChannelId, GuildId, InteractionId, MessageId, RoleId, UserId};
+
+use twilight_http::Client;
+
+use super::{defer_after, DeferGuard, Response, ResponseState, ResponseType};
+
+use std::fmt::{self, Display, Formatter, Write};
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::Error;
+
+/// An easy way to index into a message component interaction.
+pub struct Arguments<'a> {
+    top: &'a MessageComponentInteraction,
+    state: Arc<ResponseState>,
+}
+
+impl<'a> Arguments<'a> {
+    /// Create a new `Arguments`.
+    pub fn new(top: &'a MessageComponentInteraction) -> Arguments<'a> {
+        Arguments {
+            top,
+            state: Arc::default(),
+        }
+    }
+
+    /// The full `custom_id` of the component.
+    pub fn custom_id(&self) -> &'a str {
+        &self.top.data.custom_id
+    }
+
+    /// The part of the `custom_id` before the first [`SEPERATOR`].
+    ///
+    /// This is what the [`Router`](crate::service::Router) routes on.
+    pub fn prefix(&self) -> &'a str {
+        let custom_id = self.custom_id();
+
+        match custom_id.find(SEPERATOR) {
+            Some(idx) => &custom_id[..idx],
+            None => custom_id,
+        }
+    }
+
+    /// Decodes the state stored in the `custom_id` after the prefix.
+    ///
+    /// See [`custom_id`] for how to store state.
+    pub fn state<S: State>(&self) -> Result<S, StateError> {
+        decode_state(self.custom_id())
+    }
+
+    /// The values a user selected, if the component is a select menu.
+    pub fn values(&self) -> &'a [String] {
+        &self.top.data.values
+    }
+
+    /// The message the component is attached to.
+    pub fn message(&self) -> &'a Message {
+        &self.top.message
+    }
+
+    /// The interaction's token.
+    pub fn token(&self) -> &'a str {
+        &self.top.token
+    }
+
+    /// The id of the interaction.
+    pub fn id(&self) -> InteractionId {
+        self.top.id
+    }
+
+    /// The guild id of the interaction.
+    pub fn guild_id(&self) -> Option<GuildId> {
+        self.top.guild_id
+    }
+
+    /// The id of the user that executed the interaction.
+    ///
+    /// # Panics
+    /// Panics if both `member` and `user` are missing.
+    pub fn user_id(&self) -> UserId {
+        self.top
+            .member
+            .as_ref()
+            .and_then(|member| member.user.as_ref())
+            .or(self.top.user.as_ref())
+            .map(|user| user.id)
+            .expect("both `member` and `user` are missing!")
+    }
+
+    /// The roles of the member that executed the interaction.
+    ///
+    /// This is empty if the interaction wasn't in a guild.
+    pub fn roles(&self) -> &'a [RoleId] {
+        self.top
+            .member
+            .as_ref()
+            .map(|member| member.roles.as_slice())
+            .unwrap_or_default()
+    }
+
+    /// Starts building a [`Response`].
+    ///
+    /// If the interaction was deferred, this edits the original response.
+    pub fn respond(&self) -> Response<'_> {
+        self.response(ResponseType::Initial)
+    }
+
+    /// Starts building a [`Response`] for a followup.
+    pub fn followup(&self) -> Response<'_> {
+        self.response(ResponseType::Followup)
+    }
+
+    /// Starts building a deferred "thinking..." [`Response`].
+    ///
+    /// Responses made with [`Arguments::respond`] after this edit the
+    /// original response.
+    pub fn defer(&self) -> Response<'_> {
+        self.response(ResponseType::Deferred)
+    }
+
+    /// Starts building an update of the message the component is attached
+    /// to.
+    ///
+    /// If the interaction was deferred, this edits the message instead.
+    pub fn update(&self) -> Response<'_> {
+        self.response(ResponseType::Update)
+    }
+
+    /// Starts building a deferred update of the message the component is
+    /// attached to.
+    ///
+    /// Responses made with [`Arguments::update`] after this edit the message.
+    pub fn defer_update(&self) -> Response<'_> {
+        self.response(ResponseType::DeferredUpdate)
+    }
+
+    /// Starts building an edit of the original response.
+    pub fn edit(&self) -> Response<'_> {
+        self.response(ResponseType::EditOriginal)
+    }
+
+    /// Starts building an edit of a followup message.
+    pub fn edit_followup(&self, message_id: MessageId) -> Response<'_> {
+        self.response(ResponseType::EditFollowup(message_id))
+    }
+
+    /// Deletes the original response.
+    pub async fn delete_original(&self, client: &Client) -> Result<(), Error> {
+        client
+            .delete_interaction_original(&self.top.token)?
+            .exec()
+            .await
+            .map(|_| ())
+            .map_err(From::from)
+    }
+
+    /// Deletes a followup message.
+    pub async fn delete_followup(
+        &self,
+        client: &Client,
+        message_id: MessageId,
+    ) -> Result<(), Error> {
+        client
+            .delete_followup_message(&self.top.token, message_id)?
+            .exec()
+            .await
+            .map(|_| ())
+            .map_err(From::from)
+    }
+
+    /// Defers the interaction if it hasn't been responded to after `after`.
+    ///
+    /// Hold on to the returned guard until the handler is finished. This
+    /// defers an update of the message, like
+    /// [`Arguments::defer_update`].
+    #[must_use = "the deferral is cancelled when the guard is dropped"]
+    pub fn defer_after(&self, client: &Client, after: Duration) -> DeferGuard {
+        defer_after(
+            client,
+            self.top.id,
+            &self.top.token,
+            self.state.clone(),
+            after,
+            ResponseType::DeferredUpdate,
+            false,
+        )
+    }
+
+    fn response(&self, ty: ResponseType) -> Response<'_> {
+        Response::new(self.top.id, &self.top.token, ty).with_state(self.state.clone())
+    }
+}
+
+/// The character that seperates the fields of a `custom_id`.
+pub const SEPERATOR: char = ':';
+
+/// The longest a `custom_id` may be.
+pub const CUSTOM_ID_LENGTH: usize = 100;
+
+/// Creates a `custom_id` that routes to the component handler registered for
+/// `prefix`, and carries `state` with it.
+///
+/// # Panics
+/// Panics if the `custom_id` is longer than Discord allows.
+pub fn custom_id<S: State>(prefix: &str, state: &S) -> String {
+    let mut custom_id = String::from(prefix);
+
+    state.encode(&mut custom_id);
+
+    assert!(
+        custom_id.len() <= CUSTOM_ID_LENGTH,
+        "custom_id {:?} is too long",
+        custom_id,
+    );
+
+    custom_id
+}
+
+fn decode_state<S: State>(custom_id: &str) -> Result<S, StateError> {
+    let mut fields = custom_id.split(SEPERATOR);
+
+    // skip the prefix
+    fields.next();
+
+    let state = S::decode(&mut fields)?;
+
+    if fields.next().is_some() {
+        return Err(StateError::TooLong);
+    }
+
+    Ok(state)
+}
+
+/// State stored in a component's `custom_id`.
+///
+/// You probably want to use [`state!`](crate::state) instead of implementing
+/// this yourself.
+pub trait State: Sized {
+    /// Appends each field to `buf`, each prefixed with [`SEPERATOR`].
+    fn encode(&self, buf: &mut String);
+
+    /// Decodes the state from the fields of a `custom_id`.
+    fn decode<'a, I>(fields: &mut I) -> Result<Self, StateError>
+    where
+        I: Iterator<Item = &'a str>;
+}
+
+/// No state at all.
+impl State for () {
+    fn encode(&self, _buf: &mut String) {}
+
+    fn decode<'a, I>(_fields: &mut I) -> Result<Self, StateError>
+    where
+        I: Iterator<Item = &'a str>,
+    {
+        Ok(())
+    }
+}
+
+/// A single field of a [`State`].
+pub trait Field: Sized {
+    /// Writes the field to `buf`.
+    ///
+    /// This must never write a [`SEPERATOR`].
+    fn encode(&self, buf: &mut String);
+
+    /// Decodes the field.
+    fn decode(field: &str) -> Option<Self>;
+}
+
+macro_rules! impl_field_display {
+    ($($ty:ty),*) => {
+        $(
+            impl Field for $ty {
+                fn encode(&self, buf: &mut String) {
+                    write!(buf, "{}", self).unwrap();
+                }
+
+                fn decode(field: &str) -> Option<Self> {
+                    field.parse().ok()
+                }
+            }
+        )*
+    }
+}
+
+impl_field_display!(u8, u16, u32, u64, i8, i16, i32, i64, bool);
+
+macro_rules! impl_field_id {
+    ($($ty:ident),*) => {
+        $(
+            impl Field for $ty {
+                fn encode(&self, buf: &mut String) {
+                    write!(buf, "{}", self.0).unwrap();
+                }
+
+                fn decode(field: &str) -> Option<Self> {
+                    field.parse().ok().map($ty)
+                }
+            }
+        )*
+    }
+}
+
+impl_field_id!(ChannelId, GuildId, MessageId, RoleId, UserId);
+
+impl Field for String {
+    fn encode(&self, buf: &mut String) {
+        // escape the seperator and the escape character itself
+        for ch in self.chars() {
+            match ch {
+                '%' => buf.push_str("%25"),
+                SEPERATOR => buf.push_str("%3A"),
+                ch => buf.push(ch),
+            }
+        }
+    }
+
+    fn decode(field: &str) -> Option<Self> {
+        Some(field.replace("%3A", ":").replace("%25", "%"))
+    }
+}
+
+impl<T: Field> Field for Option<T> {
+    fn encode(&self, buf: &mut String) {
+        // an empty field is `None`
+        if let Some(value) = self {
+            value.encode(buf);
+        }
+    }
+
+    fn decode(field: &str) -> Option<Self> {
+        if field.is_empty() {
+            Some(None)
+        } else {
+            T::decode(field).map(Some)
+        }
+    }
+}
+
+/// An error returned by [`Arguments::state`].
+#[derive(Debug)]
+pub enum StateError {
+    /// A field is missing from the `custom_id`.
+    Missing(&'static str),
+    /// A field couldn't be decoded.
+    Invalid(&'static str),
+    /// There are more fields in the `custom_id` than expected.
+    TooLong,
+}
+
+impl Display for StateError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            StateError::Missing(name) => write!(f, "missing custom_id field {}", name),
+            StateError::Invalid(name) => write!(f, "invalid custom_id field {}", name),
+            StateError::TooLong => f.write_str("custom_id has too many fields"),
+        }
+    }
+}
+
+impl std::error::Error for StateError {}
+
+/// Macro for declaring [`State`] carried in a `custom_id`.
+///
+/// Each field is stored in order, so reordering fields will break components
+/// on messages that were already sent.
+///
+/// ```ignore
+/// state! {
+///     /// The state of the `/top` page buttons.
+///     pub struct PageState {
+///         page: u64,
+///     }
+/// }
+/// ```
+#[macro_export]
+macro_rules! state {
+    {
+        $(#[$meta:meta])*
+        $vis:vis struct $name:ident {
+            $(
+                $(#[$field_meta:meta])*
+                $field:ident: $ty:ty,
+            )*
+        }
+    } => {
+        $(#[$meta])*
+        $vis struct $name {
+            $(
+                $(#[$field_meta])*
+                pub $field: $ty,
+            )*
+        }
+
+        impl $crate::command::component::State for $name {
+            fn encode(&self, buf: &mut ::std::string::String) {
+                $(
+                    buf.push($crate::command::component::SEPERATOR);
+                    $crate::command::component::Field::encode(&self.$field, buf);
+                )*
+            }
+
+            #[allow(unused_variables)]
+            fn decode<'a, I>(
+                fields: &mut I,
+            ) -> ::std::result::Result<Self, $crate::command::component::StateError>
+            where
+                I: ::std::iter::Iterator<Item = &'a str>,
+            {
+                Ok($name {
+                    $(
+                        $field: {
+                            let field = fields.next().ok_or(
+                                $crate::command::component::StateError::Missing(stringify!($field)),
+                            )?;
+
+                            <$ty as $crate::command::component::Field>::decode(field).ok_or(
+                                $crate::command::component::StateError::Invalid(stringify!($field)),
+                            )?
+                        },
+                    )*
+                })
+            }
+        }
+    }
+}
+
+/// Creates a button that sends an interaction when clicked.
+pub fn button(
+    style: ButtonStyle,
+    custom_id: impl Into<String>,
+    label: impl Into<String>,
+) -> Button {
+    Button {
+        style,
+        label: Some(label.into()),
+        url: None,
+        disabled: false,
+        custom_id: Some(custom_id.into()),
+        emoji: None,
+    }
+}
+
+/// Creates a button that opens a link when clicked.
+pub fn link_button(label: impl Into<String>, url: impl Into<String>) -> Button {
+    Button {
+        style: ButtonStyle::Link,
+        label: Some(label.into()),
+        url: Some(url.into()),
+        disabled: false,
+        custom_id: None,
+        emoji: None,
+    }
+}
+
+/// Creates a select menu that lets a user pick one of `options`.
+pub fn select_menu(custom_id: impl Into<String>, options: Vec<SelectMenuOption>) -> SelectMenu {
+    SelectMenu {
+        custom_id: custom_id.into(),
+        disabled: false,
+        max_values: None,
+        min_values: None,
+        options,
+        placeholder: None,
+    }
+}
+
+/// Creates an option for a select menu.
+pub fn select_option(label: impl Into<String>, value: impl Into<String>) -> SelectMenuOption {
+    SelectMenuOption {
+        default: false,
+        description: None,
+        emoji: None,
+        label: label.into(),
+        value: value.into(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{custom_id, decode_state, Field, StateError, SEPERATOR};
+
+    use twilight_model::id::{ChannelId, RoleId};
+
+    state! {
+        #[derive(Debug, PartialEq)]
+        struct TestState {
+            name: String,
+            note: Option<String>,
+            channel_id: ChannelId,
+            role_id: Option<RoleId>,
+            page: u64,
+            enabled: bool,
+        }
+    }
+
+    fn state(name: &str, note: Option<&str>) -> TestState {
+        TestState {
+            name: String::from(name),
+            note: note.map(String::from),
+            channel_id: ChannelId(896_364_389_612_601_344),
+            role_id: Some(RoleId(1)),
+            page: 3,
+            enabled: true,
+        }
+    }
+
+    fn round_trip(state: TestState) {
+        let custom_id = custom_id("test", &state);
+
+        assert_eq!(
+            decode_state::<TestState>(&custom_id).unwrap(),
+            state,
+            "{:?}",
+            custom_id,
+        );
+    }
+
+    #[test]
+    fn string_escaping() {
+        let strings = [
+            ("", ""),
+            ("plain", "plain"),
+            (":", "%3A"),
+            ("a:b:c", "a%3Ab%3Ac"),
+            ("%", "%25"),
+            // an escape sequence typed by a user isn't unescaped
+            ("%3A", "%253A"),
+            ("%25", "%2525"),
+            ("%:", "%25%3A"),
+        ];
+
+        for (string, escaped) in strings {
+            let mut buf = String::new();
+            String::from(string).encode(&mut buf);
+
+            assert_eq!(buf, escaped);
+            assert!(!buf.contains(SEPERATOR));
+            assert_eq!(String::decode(&buf).as_deref(), Some(string));
+        }
+    }
+
+    #[test]
+    fn custom_id_has_prefix() {
+        assert_eq!(custom_id("test", &()), "test");
+        assert_eq!(
+            custom_id("test", &state("a:b", None)),
+            "test:a%3Ab::896364389612601344:1:3:true",
+        );
+    }
+
+    #[test]
+    fn state_round_trip() {
+        round_trip(state("", None));
+        round_trip(state("name", Some("note")));
+        round_trip(state("a:b", Some(":")));
+        round_trip(state("100%", Some("%3A")));
+        round_trip(TestState {
+            role_id: None,
+            page: u64::MAX,
+            enabled: false,
+            ..state("::", Some("%%"))
+        });
+    }
+
+    #[test]
+    fn no_state() {
+        decode_state::<()>("test").unwrap();
+
+        assert!(matches!(
+            decode_state::<()>("test:"),
+            Err(StateError::TooLong)
+        ));
+    }
+
+    #[test]
+    fn invalid_state() {
+        assert!(matches!(
+            decode_state::<TestState>("test:name:note:1:2"),
+            Err(StateError::Missing("page")),
+        ));
+        assert!(matches!(
+            decode_state::<TestState>("test:name:note:channel:2:3:true"),
+            Err(StateError::Invalid("channel_id")),
+        ));
+        assert!(matches!(
+            decode_state::<TestState>("test:name:note:1:2:3:yes"),
+            Err(StateError::Invalid("enabled")),
+        ));
+        assert!(matches!(
+            decode_state::<TestState>("test:name:note:1:2:3:true:extra"),
+            Err(StateError::TooLong),
+        ));
+    }
+
+    #[test]
+    #[should_panic]
+    fn custom_id_too_long() {
+        custom_id("test", &state(&"a".repeat(100), None));
+    }
+}
@@ -1,13 +1,26 @@
 //! Command utilities.
 
 pub mod chat;
+pub mod component;
+pub mod spec;
+pub mod sync;
 
 use twilight_model::application::callback::{CallbackData, InteractionResponse};
+use twilight_model::application::component::{action_row::ActionRow, Component};
+use twilight_model::channel::embed::Embed;
 use twilight_model::channel::message::{AllowedMentions, MessageFlags};
-use twilight_model::id::InteractionId;
+use twilight_model::channel::Message;
+use twilight_model::id::{InteractionId, MessageId};
 
 use twilight_http::Client;
 
+use tokio::sync::Mutex;
+use tokio::task::JoinHandle;
+use tokio::time::sleep;
+
+use std::sync::Arc;
+use std::time::Duration;
+
 use anyhow::Error;
 
 /// A response to an interaction.
@@ -16,6 +29,8 @@ pub struct Response<'a> {
     token: &'a str,
     ty: ResponseType,
     data: CallbackData,
+    files: Vec<(String, Vec<u8>)>,
+    state: Option<Arc<ResponseState>>,
 }
 
 impl<'a> Response<'a> {
@@ -33,16 +48,71 @@ impl<'a> Response<'a> {
                 flags: None,
                 tts: None,
             },
+            files: Vec::new(),
+            state: None,
         }
     }
 
+    /// Tracks the response in a [`ResponseState`].
+    ///
+    /// An [`Initial`](ResponseType::Initial) response of an interaction that
+    /// was deferred in the meantime will edit the original response instead.
+    pub fn with_state(mut self, state: Arc<ResponseState>) -> Self {
+        self.state = Some(state);
+        self
+    }
+
     /// Sets the response's content.
     pub fn content(mut self, content: impl Into<String>) -> Self {
         self.data.content = Some(content.into());
         self
     }
 
+    /// Adds an embed to the response.
+    pub fn embed(mut self, embed: Embed) -> Self {
+        self.data.embeds.push(embed);
+        self
+    }
+
+    /// Adds a row of components to the response.
+    ///
+    /// See the helpers in [`component`] for building buttons and select
+    /// menus.
+    pub fn action_row(mut self, components: impl IntoIterator<Item = Component>) -> Self {
+        self.data
+            .components
+            .get_or_insert_with(Vec::new)
+            .push(Component::ActionRow(ActionRow {
+                components: components.into_iter().collect(),
+            }));
+        self
+    }
+
+    /// Removes every component from the message.
+    ///
+    /// This is only useful when editing a message.
+    pub fn clear_components(mut self) -> Self {
+        self.data.components = Some(Vec::new());
+        self
+    }
+
+    /// Sets who the response is allowed to ping.
+    ///
+    /// By default, a response will not ping anyone.
+    pub fn allowed_mentions(mut self, allowed_mentions: AllowedMentions) -> Self {
+        self.data.allowed_mentions = Some(allowed_mentions);
+        self
+    }
+
+    /// Attaches a file to the response.
+    pub fn file(mut self, name: impl Into<String>, data: impl Into<Vec<u8>>) -> Self {
+        self.files.push((name.into(), data.into()));
+        self
+    }
+
     /// Marks the response as ephemeral.
+    ///
+    /// This does nothing for edits.
     pub fn ephemeral(mut self) -> Self {
         *self.data.flags.get_or_insert(MessageFlags::empty()) |= MessageFlags::EPHEMERAL;
         self
@@ -50,13 +120,73 @@ impl<'a> Response<'a> {
 
     /// Sends the response.
     pub async fn exec(self, client: &Client) -> Result<(), Error> {
-        match self.ty {
-            ResponseType::Initial => self.exec_initial(client).await,
-            ResponseType::Followup => self.exec_followup(client).await,
+        self.exec_message(client).await.map(|_| ())
+    }
+
+    /// Sends the response, returning the message that was created or edited.
+    ///
+    /// Discord doesn't return a message for initial or deferred responses, or
+    /// for edits of followups.
+    pub async fn exec_message(self, client: &Client) -> Result<Option<Message>, Error> {
+        let state = match self.state.clone() {
+            Some(state) => state,
+            None => {
+                let ty = self.ty;
+                return self.exec_as(ty, client).await;
+            }
+        };
+
+        let mut progress = state.0.lock().await;
+
+        let ty = match (self.ty, *progress) {
+            // someone else deferred the interaction before we got to it
+            (ResponseType::Initial | ResponseType::Update, Progress::Deferred) => {
+                ResponseType::EditOriginal
+            }
+            (
+                ResponseType::Deferred | ResponseType::DeferredUpdate,
+                Progress::Deferred | Progress::Done,
+            ) => return Ok(None),
+            (ty, _) => ty,
+        };
+
+        let message = self.exec_as(ty, client).await?;
+
+        match ty {
+            ResponseType::Initial | ResponseType::Update | ResponseType::EditOriginal => {
+                *progress = Progress::Done
+            }
+            ResponseType::Deferred | ResponseType::DeferredUpdate => *progress = Progress::Deferred,
+            _ => (),
+        }
+
+        Ok(message)
+    }
+
+    async fn exec_as(self, ty: ResponseType, client: &Client) -> Result<Option<Message>, Error> {
+        match ty {
+            ResponseType::Initial => self.exec_initial(client).await.map(|_| None),
+            ResponseType::Deferred => self.exec_deferred(client).await.map(|_| None),
+            ResponseType::Update => self.exec_update(client).await.map(|_| None),
+            ResponseType::DeferredUpdate => self.exec_deferred_update(client).await.map(|_| None),
+            ResponseType::Followup => self.exec_followup(client).await.map(Some),
+            ResponseType::EditOriginal => self.exec_edit_original(client).await.map(Some),
+            ResponseType::EditFollowup(message_id) => self
+                .exec_edit_followup(client, message_id)
+                .await
+                .map(|_| None),
         }
     }
 
     async fn exec_initial(self, client: &Client) -> Result<(), Error> {
+        if !self.files.is_empty() {
+            // the interaction callback can't carry files, so we defer and
+            // edit the original response instead
+            self.exec_deferred(client).await?;
+
+            return self.exec_edit_original(client).await.map(|_| ());
+        }
+
         let response = InteractionResponse::ChannelMessageWithSource(self.data);
 
         client
@@ -67,22 +197,236 @@ impl<'a> Response<'a> {
             .map_err(From::from)
     }
 
-    async fn exec_followup(self, client: &Client) -> Result<(), Error> {
+    async fn exec_update(self, client: &Client) -> Result<(), Error> {
+        if !self.files.is_empty() {
+            // same deal as `exec_initial`
+            self.exec_deferred_update(client).await?;
+
+            return self.exec_edit_original(client).await.map(|_| ());
+        }
+
+        let response = InteractionResponse::UpdateMessage(self.data);
+
+        client
+            .interaction_callback(self.id, self.token, &response)
+            .exec()
+            .await
+            .map(|_| ())
+            .map_err(From::from)
+    }
+
+    async fn exec_deferred_update(&self, client: &Client) -> Result<(), Error> {
+        client
+            .interaction_callback(
+                self.id,
+                self.token,
+                &InteractionResponse::DeferredUpdateMessage,
+            )
+            .exec()
+            .await
+            .map(|_| ())
+            .map_err(From::from)
+    }
+
+    async fn exec_deferred(&self, client: &Client) -> Result<(), Error> {
+        let response = InteractionResponse::DeferredChannelMessageWithSource(CallbackData {
+            allowed_mentions: None,
+            components: None,
+            content: None,
+            embeds: Vec::new(),
+            flags: self.data.flags,
+            tts: None,
+        });
+
+        client
+            .interaction_callback(self.id, self.token, &response)
+            .exec()
+            .await
+            .map(|_| ())
+            .map_err(From::from)
+    }
+
+    async fn exec_followup(self, client: &Client) -> Result<Message, Error> {
+        let files = self.files();
         let mut req = client.create_followup_message(self.token)?;
 
         if let Some(content) = self.data.content.as_ref() {
             req = req.content(content);
         }
 
+        if !self.data.embeds.is_empty() {
+            req = req.embeds(&self.data.embeds);
+        }
+
+        if let Some(components) = self.data.components.as_ref() {
+            req = req.components(components)?;
+        }
+
+        if let Some(allowed_mentions) = self.data.allowed_mentions.as_ref() {
+            req = req.allowed_mentions(allowed_mentions);
+        }
+
+        if !files.is_empty() {
+            req = req.files(&files);
+        }
+
         if let Some(flags) = self.data.flags {
             req = req.ephemeral(flags.contains(MessageFlags::EPHEMERAL));
         }
 
+        req.exec().await?.model().await.map_err(From::from)
+    }
+
+    async fn exec_edit_original(self, client: &Client) -> Result<Message, Error> {
+        let files = self.files();
+        let mut req = client.update_interaction_original(self.token)?;
+
+        // only touch what was set, so edits don't wipe the message
+        if let Some(content) = self.data.content.as_deref() {
+            req = req.content(Some(content))?;
+        }
+
+        if !self.data.embeds.is_empty() {
+            req = req.embeds(Some(&self.data.embeds))?;
+        }
+
+        if let Some(components) = self.data.components.as_deref() {
+            req = req.components(Some(components))?;
+        }
+
+        if let Some(allowed_mentions) = self.data.allowed_mentions.clone() {
+            req = req.allowed_mentions(allowed_mentions);
+        }
+
+        if !files.is_empty() {
+            req = req.files(&files);
+        }
+
+        req.exec().await?.model().await.map_err(From::from)
+    }
+
+    async fn exec_edit_followup(self, client: &Client, message_id: MessageId) -> Result<(), Error> {
+        let files = self.files();
+        let mut req = client.update_followup_message(self.token, message_id)?;
+
+        // only touch what was set, so edits don't wipe the message
+        if let Some(content) = self.data.content.as_deref() {
+            req = req.content(Some(content))?;
+        }
+
+        if !self.data.embeds.is_empty() {
+            req = req.embeds(Some(&self.data.embeds))?;
+        }
+
+        if let Some(components) = self.data.components.as_deref() {
+            req = req.components(Some(components))?;
+        }
+
+        if let Some(allowed_mentions) = self.data.allowed_mentions.clone() {
+            req = req.allowed_mentions(allowed_mentions);
+        }
+
+        if !files.is_empty() {
+            req = req.files(&files);
+        }
+
         req.exec().await.map(|_| ()).map_err(From::from)
     }
+
+    fn files(&self) -> Vec<(&str, &[u8])> {
+        self.files
+            .iter()
+            .map(|(name, data)| (name.as_str(), data.as_slice()))
+            .collect()
+    }
 }
 
+/// How a [`Response`] is sent.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum ResponseType {
+    /// The first response to an interaction.
     Initial,
+    /// A "thinking..." response, to be edited with
+    /// [`EditOriginal`](ResponseType::EditOriginal) later.
+    Deferred,
+    /// A new message after the first response.
     Followup,
+    /// Edits the first response.
+    EditOriginal,
+    /// Edits a followup message.
+    EditFollowup(MessageId),
+    /// Edits the message a component is attached to.
+    ///
+    /// Only valid for component interactions.
+    Update,
+    /// Acknowledges a component interaction, to edit the message with
+    /// [`EditOriginal`](ResponseType::EditOriginal) later.
+    ///
+    /// Only valid for component interactions.
+    DeferredUpdate,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Progress {
+    Pending,
+    Deferred,
+    Done,
+}
+
+/// Keeps track of how far along responding to an interaction is.
+///
+/// This is shared between every [`Response`] of an interaction, so that a
+/// handler doesn't need to care if the interaction was automatically deferred
+/// by [`defer_after`].
+#[derive(Debug)]
+pub struct ResponseState(Mutex<Progress>);
+
+impl Default for ResponseState {
+    fn default() -> ResponseState {
+        ResponseState(Mutex::new(Progress::Pending))
+    }
+}
+
+/// Defers an interaction if it hasn't been responded to after a while.
+///
+/// `ty` should be either [`ResponseType::Deferred`] or
+/// [`ResponseType::DeferredUpdate`]. The timer is cancelled when the returned
+/// [`DeferGuard`] is dropped, so keep it alive for as long as the handler
+/// runs.
+pub fn defer_after(
+    client: &Client,
+    id: InteractionId,
+    token: &str,
+    state: Arc<ResponseState>,
+    after: Duration,
+    ty: ResponseType,
+    ephemeral: bool,
+) -> DeferGuard {
+    let client = client.clone();
+    let token = String::from(token);
+
+    DeferGuard(tokio::spawn(async move {
+        sleep(after).await;
+
+        let mut response = Response::new(id, &token, ty).with_state(state);
+
+        if ephemeral {
+            response = response.ephemeral();
+        }
+
+        if let Err(err) = response.exec(&client).await {
+            error!("failed to defer interaction: {}", err);
+        }
+    }))
+}
+
+/// Cancels an automatic deferral when dropped.
+///
+/// See [`defer_after`].
+pub struct DeferGuard(JoinHandle<()>);
+
+impl Drop for DeferGuard {
+    fn drop(&mut self) {
+        self.0.abort();
+    }
 }
@@ -0,0 +1,516 @@
+//! Declarative command definitions.
+//!
+//! A [`CommandSpec`] describes a chat command once, and is used to register
+//! the command with Discord, to route it to its handlers and, through the
+//! [`Options`] types it is built from, to parse the arguments a handler
+//! receives. Options are declared with the [`options!`](crate::options)
+//! macro, so the option names sent to Discord are the same identifiers the
+//! handlers read.
+
+use twilight_model::application::command::{
+    BaseCommandOptionData, ChoiceCommandOptionData, Command, CommandOption, CommandType,
+    OptionsCommandOptionData,
+};
+use twilight_model::id::{ChannelId, RoleId, UserId};
+
+use super::chat::{ArgError, Arguments};
+use crate::service::router::CommandHandler;
+use crate::service::Command as Handler;
+
+use std::fmt::{self, Debug, Formatter};
+use std::sync::Arc;
+
+/// A set of typed options, parsed from [`Arguments`].
+///
+/// You probably want to use [`options!`](crate::options) instead of
+/// implementing this yourself.
+pub trait Options: Sized {
+    /// The option definitions sent to Discord.
+    fn options() -> Vec<CommandOption>;
+
+    /// Parses the options out of the arguments of an interaction.
+    fn parse(args: &Arguments<'_>) -> Result<Self, ArgError>;
+}
+
+/// No options at all.
+impl Options for () {
+    fn options() -> Vec<CommandOption> {
+        Vec::new()
+    }
+
+    fn parse(_args: &Arguments<'_>) -> Result<Self, ArgError> {
+        Ok(())
+    }
+}
+
+/// A type that can be used as a single command option.
+pub trait Argument: Sized {
+    /// Creates the option definition.
+    fn option(name: String, description: String, required: bool) -> CommandOption;
+
+    /// Gets the argument by name, if it was passed.
+    fn get(args: &Arguments<'_>, name: &str) -> Result<Option<Self>, ArgError>;
+}
+
+/// A field in an [`Options`] struct.
+///
+/// This is implemented for every [`Argument`] as a required option, and for
+/// `Option<T>` as an optional one.
+pub trait Field: Sized {
+    /// Creates the option definition.
+    fn option(name: &str, description: &str) -> CommandOption;
+
+    /// Parses the field.
+    fn parse(args: &Arguments<'_>, name: &'static str) -> Result<Self, ArgError>;
+}
+
+impl<T> Field for T
+where
+    T: Argument,
+{
+    fn option(name: &str, description: &str) -> CommandOption {
+        T::option(String::from(name), String::from(description), true)
+    }
+
+    fn parse(args: &Arguments<'_>, name: &'static str) -> Result<Self, ArgError> {
+        T::get(args, name)?.ok_or(ArgError::Missing(name))
+    }
+}
+
+impl<T> Field for Option<T>
+where
+    T: Argument,
+{
+    fn option(name: &str, description: &str) -> CommandOption {
+        T::option(String::from(name), String::from(description), false)
+    }
+
+    fn parse(args: &Arguments<'_>, name: &'static str) -> Result<Self, ArgError> {
+        T::get(args, name)
+    }
+}
+
+impl Argument for String {
+    fn option(name: String, description: String, required: bool) -> CommandOption {
+        CommandOption::String(ChoiceCommandOptionData {
+            choices: Vec::new(),
+            description,
+            name,
+            required,
+        })
+    }
+
+    fn get(args: &Arguments<'_>, name: &str) -> Result<Option<Self>, ArgError> {
+        args.get_string(name).map(|s| s.map(String::from))
+    }
+}
+
+impl Argument for i64 {
+    fn option(name: String, description: String, required: bool) -> CommandOption {
+        CommandOption::Integer(ChoiceCommandOptionData {
+            choices: Vec::new(),
+            description,
+            name,
+            required,
+        })
+    }
+
+    fn get(args: &Arguments<'_>, name: &str) -> Result<Option<Self>, ArgError> {
+        args.get_integer(name)
+    }
+}
+
+impl Argument for bool {
+    fn option(name: String, description: String, required: bool) -> CommandOption {
+        CommandOption::Boolean(BaseCommandOptionData {
+            description,
+            name,
+            required,
+        })
+    }
+
+    fn get(args: &Arguments<'_>, name: &str) -> Result<Option<Self>, ArgError> {
+        args.get_boolean(name)
+    }
+}
+
+impl Argument for UserId {
+    fn option(name: String, description: String, required: bool) -> CommandOption {
+        CommandOption::User(BaseCommandOptionData {
+            description,
+            name,
+            required,
+        })
+    }
+
+    fn get(args: &Arguments<'_>, name: &str) -> Result<Option<Self>, ArgError> {
+        args.get_user(name).map(|user| user.map(|user| user.id))
+    }
+}
+
+impl Argument for RoleId {
+    fn option(name: String, description: String, required: bool) -> CommandOption {
+        CommandOption::Role(BaseCommandOptionData {
+            description,
+            name,
+            required,
+        })
+    }
+
+    fn get(args: &Arguments<'_>, name: &str) -> Result<Option<Self>, ArgError> {
+        args.get_role(name).map(|role| role.map(|role| role.id))
+    }
+}
+
+impl Argument for ChannelId {
+    fn option(name: String, description: String, required: bool) -> CommandOption {
+        CommandOption::Channel(BaseCommandOptionData {
+            description,
+            name,
+            required,
+        })
+    }
+
+    fn get(args: &Arguments<'_>, name: &str) -> Result<Option<Self>, ArgError> {
+        args.get_channel(name)
+            .map(|channel| channel.map(|channel| channel.id))
+    }
+}
+
+/// The definition of a chat command, along with its handlers.
+///
+/// Every command that can be run needs a handler: either the command itself,
+/// with [`CommandSpec::handler`], or each of its subcommands, which are
+/// given one when they are added.
+#[derive(Clone)]
+pub struct CommandSpec {
+    name: String,
+    description: String,
+    default_permission: bool,
+    options: Vec<CommandOption>,
+    /// The handlers by their path below this command, which is empty for the
+    /// command itself.
+    handlers: Vec<(String, Arc<dyn CommandHandler>)>,
+}
+
+impl CommandSpec {
+    /// Creates a new chat command with no options.
+    pub fn new(name: impl Into<String>, description: impl Into<String>) -> CommandSpec {
+        CommandSpec {
+            name: name.into(),
+            description: description.into(),
+            default_permission: true,
+            options: Vec::new(),
+            handlers: Vec::new(),
+        }
+    }
+
+    /// Sets the handler of the command.
+    ///
+    /// This is for commands without subcommands.
+    pub fn handler<H>(mut self, handler: H) -> Self
+    where
+        H: for<'f> Handler<'f> + Send + Sync + 'static,
+    {
+        self.handlers.push((String::new(), Arc::new(handler)));
+        self
+    }
+
+    /// Sets the options of the command.
+    pub fn options<O: Options>(mut self) -> Self {
+        self.options = O::options();
+        self
+    }
+
+    /// Adds a subcommand to the command, handled by `handler`.
+    pub fn subcommand<O: Options>(
+        mut self,
+        name: impl Into<String>,
+        description: impl Into<String>,
+        handler: impl for<'f> Handler<'f> + Send + Sync + 'static,
+    ) -> Self {
+        let name = name.into();
+
+        self.handlers.push((name.clone(), Arc::new(handler)));
+        self.options
+            .push(CommandOption::SubCommand(OptionsCommandOptionData {
+                name,
+                description: description.into(),
+                options: O::options(),
+                required: false,
+            }));
+        self
+    }
+
+    /// Adds a subcommand group to the command.
+    ///
+    /// The group is built like any other command, but it may only have
+    /// subcommands.
+    pub fn group(mut self, group: CommandSpec) -> Self {
+        for (path, handler) in group.handlers {
+            self.handlers
+                .push((format!("{} {}", group.name, path), handler));
+        }
+
+        self.options
+            .push(CommandOption::SubCommandGroup(OptionsCommandOptionData {
+                name: group.name,
+                description: group.description,
+                options: group.options,
+                required: false,
+            }));
+        self
+    }
+
+    /// Sets whether the command is enabled for everyone by default.
+    pub fn default_permission(mut self, default_permission: bool) -> Self {
+        self.default_permission = default_permission;
+        self
+    }
+
+    /// The name of the command.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The description of the command.
+    pub fn description(&self) -> &str {
+        &self.description
+    }
+
+    /// Whether the command is enabled for everyone by default.
+    pub fn is_default_permission(&self) -> bool {
+        self.default_permission
+    }
+
+    /// The option definitions of the command.
+    pub fn command_options(&self) -> &[CommandOption] {
+        &self.options
+    }
+
+    /// Every handler of the command, by the full path it handles.
+    ///
+    /// The path is the command name followed by any subcommand names,
+    /// seperated by spaces, like the [`Router`](crate::service::Router)
+    /// routes on.
+    pub(crate) fn handlers(&self) -> impl Iterator<Item = (String, &Arc<dyn CommandHandler>)> {
+        self.handlers.iter().map(move |(path, handler)| {
+            if path.is_empty() {
+                (self.name.clone(), handler)
+            } else {
+                (format!("{} {}", self.name, path), handler)
+            }
+        })
+    }
+
+    /// Whether a registered command is up to date with this definition.
+    pub fn matches(&self, command: &Command) -> bool {
+        command.kind == CommandType::ChatInput
+            && command.name == self.name
+            && command.description == self.description
+            && command.default_permission.unwrap_or(true) == self.default_permission
+            && command.options == self.options
+    }
+
+    /// Creates the registration payload of the command.
+    pub fn to_command(&self) -> Command {
+        Command {
+            application_id: None,
+            default_permission: Some(self.default_permission),
+            description: self.description.clone(),
+            guild_id: None,
+            id: None,
+            kind: CommandType::ChatInput,
+            name: self.name.clone(),
+            options: self.options.clone(),
+        }
+    }
+}
+
+impl Debug for CommandSpec {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        f.debug_struct("CommandSpec")
+            .field("name", &self.name)
+            .field("description", &self.description)
+            .field("default_permission", &self.default_permission)
+            .field("options", &self.options)
+            .field(
+                "handlers",
+                &self
+                    .handlers
+                    .iter()
+                    .map(|(path, _)| path)
+                    .collect::<Vec<_>>(),
+            )
+            .finish()
+    }
+}
+
+/// Macro for declaring a set of typed command [`Options`].
+///
+/// Each field becomes an option of the same name, and its (single line) doc
+/// comment becomes the option's description. Fields of type `Option<T>` are
+/// optional, everything else is required.
+///
+/// ```ignore
+/// options! {
+///     /// Options for `/rank`.
+///     pub struct RankOptions {
+///         /// the user to check
+///         user: Option<UserId>,
+///     }
+/// }
+/// ```
+#[macro_export]
+macro_rules! options {
+    {
+        $(#[$meta:meta])*
+        $vis:vis struct $name:ident {
+            $(
+                #[doc = $desc:literal]
+                $field:ident: $ty:ty,
+            )*
+        }
+    } => {
+        $(#[$meta])*
+        $vis struct $name {
+            $(
+                #[doc = $desc]
+                pub $field: $ty,
+            )*
+        }
+
+        impl $crate::command::spec::Options for $name {
+            fn options() -> ::std::vec::Vec<::twilight_model::application::command::CommandOption> {
+                ::std::vec![
+                    $(
+                        <$ty as $crate::command::spec::Field>::option(
+                            stringify!($field),
+                            $desc.trim(),
+                        ),
+                    )*
+                ]
+            }
+
+            #[allow(unused_variables)]
+            fn parse(
+                args: &$crate::command::chat::Arguments<'_>,
+            ) -> ::std::result::Result<Self, $crate::command::chat::ArgError> {
+                Ok($name {
+                    $(
+                        $field: <$ty as $crate::command::spec::Field>::parse(
+                            args,
+                            stringify!($field),
+                        )?,
+                    )*
+                })
+            }
+        }
+    }
+}
+
+/// Macro for declaring an [`Argument`] that is one of a set of choices.
+///
+/// Each variant becomes a choice with the string value given, and its (single
+/// line) doc comment becomes the name of the choice shown to users. The enum
+/// derives `Clone`, `Copy`, `Debug`, `PartialEq` and `Eq`, and can also be
+/// used as a [`Field`](crate::command::component::Field) of component state.
+///
+/// ```ignore
+/// choices! {
+///     /// A period of time for `/top`.
+///     pub enum Period {
+///         /// this week
+///         Week = "week",
+///         /// all time
+///         AllTime = "all-time",
+///     }
+/// }
+/// ```
+#[macro_export]
+macro_rules! choices {
+    {
+        $(#[$meta:meta])*
+        $vis:vis enum $name:ident {
+            $(
+                #[doc = $desc:literal]
+                $variant:ident = $value:literal,
+            )*
+        }
+    } => {
+        $(#[$meta])*
+        #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+        $vis enum $name {
+            $(
+                #[doc = $desc]
+                $variant,
+            )*
+        }
+
+        impl $name {
+            /// The value of the choice sent by Discord.
+            pub fn value(&self) -> &'static str {
+                match self {
+                    $($name::$variant => $value,)*
+                }
+            }
+
+            fn from_value(value: &str) -> ::std::option::Option<Self> {
+                match value {
+                    $($value => ::std::option::Option::Some($name::$variant),)*
+                    _ => ::std::option::Option::None,
+                }
+            }
+        }
+
+        impl $crate::command::spec::Argument for $name {
+            fn option(
+                name: ::std::string::String,
+                description: ::std::string::String,
+                required: bool,
+            ) -> ::twilight_model::application::command::CommandOption {
+                ::twilight_model::application::command::CommandOption::String(
+                    ::twilight_model::application::command::ChoiceCommandOptionData {
+                        choices: ::std::vec![
+                            $(
+                                ::twilight_model::application::command::CommandOptionChoice::String {
+                                    name: ::std::string::String::from($desc.trim()),
+                                    value: ::std::string::String::from($value),
+                                },
+                            )*
+                        ],
+                        description,
+                        name,
+                        required,
+                    },
+                )
+            }
+
+            fn get(
+                args: &$crate::command::chat::Arguments<'_>,
+                name: &str,
+            ) -> ::std::result::Result<::std::option::Option<Self>, $crate::command::chat::ArgError> {
+                match args.get_string(name)? {
+                    ::std::option::Option::Some(value) => $name::from_value(value)
+                        .map(::std::option::Option::Some)
+                        .ok_or_else(|| {
+                            $crate::command::chat::ArgError::InvalidChoice(
+                                ::std::string::String::from(value),
+                            )
+                        }),
+                    ::std::option::Option::None => Ok(::std::option::Option::None),
+                }
+            }
+        }
+
+        impl $crate::command::component::Field for $name {
+            fn encode(&self, buf: &mut ::std::string::String) {
+                buf.push_str(self.value());
+            }
+
+            fn decode(field: &str) -> ::std::option::Option<Self> {
+                $name::from_value(field)
+            }
+        }
+    }
+}
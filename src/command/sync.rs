@@ -0,0 +1,203 @@
+//! Syncing command definitions with the commands registered on Discord.
+
+use super::spec::CommandSpec;
+
+use twilight_model::application::command::Command;
+
+/// A single step of a [`Plan`].
+pub enum Change<'a> {
+    /// The command isn't registered yet.
+    Create(&'a CommandSpec),
+    /// The command is registered, but its definition has changed.
+    Update(&'a CommandSpec, &'a Command),
+    /// The command is registered, but isn't in the manifest anymore.
+    Delete(&'a Command),
+    /// The command is registered and up to date.
+    Unchanged(&'a CommandSpec),
+}
+
+impl<'a> Change<'a> {
+    /// The name of the command the change pertains to.
+    pub fn name(&self) -> &'a str {
+        match self {
+            Change::Create(spec) | Change::Update(spec, _) | Change::Unchanged(spec) => spec.name(),
+            Change::Delete(command) => &command.name,
+        }
+    }
+
+    /// Whether the change actually does anything.
+    pub fn is_change(&self) -> bool {
+        !matches!(self, Change::Unchanged(_))
+    }
+}
+
+/// The difference between a command manifest and the registered commands.
+pub struct Plan<'a> {
+    changes: Vec<Change<'a>>,
+}
+
+impl<'a> Plan<'a> {
+    /// Computes the changes needed to bring `registered` up to date with
+    /// `manifest`.
+    pub fn new(manifest: &'a [CommandSpec], registered: &'a [Command]) -> Plan<'a> {
+        let mut changes = Vec::new();
+
+        for spec in manifest {
+            let change = match registered.iter().find(|cmd| cmd.name == spec.name()) {
+                Some(command) if spec.matches(command) => Change::Unchanged(spec),
+                Some(command) => Change::Update(spec, command),
+                None => Change::Create(spec),
+            };
+
+            changes.push(change);
+        }
+
+        for command in registered {
+            if !manifest.iter().any(|spec| spec.name() == command.name) {
+                changes.push(Change::Delete(command));
+            }
+        }
+
+        Plan { changes }
+    }
+
+    /// Every step of the plan, including unchanged commands.
+    pub fn changes(&self) -> &[Change<'a>] {
+        &self.changes
+    }
+
+    /// Whether the registered commands are already up to date.
+    pub fn is_empty(&self) -> bool {
+        !self.changes.iter().any(Change::is_change)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Change, Plan};
+    use crate::command::spec::CommandSpec;
+    use crate::options;
+
+    use twilight_model::application::command::Command;
+    use twilight_model::id::{CommandId, UserId};
+
+    options! {
+        #[allow(dead_code)]
+        struct UserOptions {
+            /// the user to check
+            user: Option<UserId>,
+            /// how many to show
+            count: Option<i64>,
+        }
+    }
+
+    options! {
+        #[allow(dead_code)]
+        struct ReorderedOptions {
+            /// how many to show
+            count: Option<i64>,
+            /// the user to check
+            user: Option<UserId>,
+        }
+    }
+
+    fn spec(name: &str) -> CommandSpec {
+        CommandSpec::new(name, "does something").options::<UserOptions>()
+    }
+
+    /// A command as Discord would return it.
+    fn registered(spec: &CommandSpec) -> Command {
+        Command {
+            id: Some(CommandId(1)),
+            ..spec.to_command()
+        }
+    }
+
+    fn changes<'a>(plan: &'a Plan) -> Vec<(&'static str, &'a str)> {
+        plan.changes()
+            .iter()
+            .map(|change| {
+                let kind = match change {
+                    Change::Create(_) => "create",
+                    Change::Update(..) => "update",
+                    Change::Delete(_) => "delete",
+                    Change::Unchanged(_) => "unchanged",
+                };
+
+                (kind, change.name())
+            })
+            .collect()
+    }
+
+    #[test]
+    fn create_update_delete_unchanged() {
+        let manifest = [spec("rank"), spec("top"), spec("info")];
+        let registered = [
+            registered(&spec("rank")),
+            registered(&CommandSpec::new("top", "does something")),
+            registered(&spec("old")),
+        ];
+
+        let plan = Plan::new(&manifest, &registered);
+
+        assert_eq!(
+            changes(&plan),
+            [
+                ("unchanged", "rank"),
+                ("update", "top"),
+                ("create", "info"),
+                ("delete", "old"),
+            ],
+        );
+        assert!(!plan.is_empty());
+    }
+
+    #[test]
+    fn up_to_date() {
+        let manifest = [spec("rank"), spec("top")];
+        let registered = [registered(&spec("top")), registered(&spec("rank"))];
+
+        let plan = Plan::new(&manifest, &registered);
+
+        assert_eq!(
+            changes(&plan),
+            [("unchanged", "rank"), ("unchanged", "top")]
+        );
+        assert!(plan.is_empty());
+    }
+
+    #[test]
+    fn option_order_is_a_change() {
+        let manifest = [spec("rank")];
+        let registered = [registered(
+            &CommandSpec::new("rank", "does something").options::<ReorderedOptions>(),
+        )];
+
+        let plan = Plan::new(&manifest, &registered);
+
+        assert_eq!(changes(&plan), [("update", "rank")]);
+    }
+
+    #[test]
+    fn description_is_a_change() {
+        let manifest = [spec("rank")];
+        let registered = [registered(
+            &CommandSpec::new("rank", "does something else").options::<UserOptions>(),
+        )];
+
+        let plan = Plan::new(&manifest, &registered);
+
+        assert_eq!(changes(&plan), [("update", "rank")]);
+        assert!(!plan.is_empty());
+    }
+
+    #[test]
+    fn default_permission_is_a_change() {
+        let manifest = [spec("rank").default_permission(false)];
+        let registered = [registered(&spec("rank"))];
+
+        let plan = Plan::new(&manifest, &registered);
+
+        assert_eq!(changes(&plan), [("update", "rank")]);
+    }
+}
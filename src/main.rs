@@ -2,22 +2,29 @@
 extern crate log;
 
 use std::env;
+use std::path::PathBuf;
 
-use kromer::bot;
-use kromer::service::{Context, Services};
+use kromer::bot::{
+    self,
+    admin::{self, PendingResets},
+    config::{self, permissions::Owners},
+    xp::Xp,
+};
+use kromer::command::spec::CommandSpec;
+use kromer::command::sync::{Change, Plan};
+use kromer::model::permissions::Owner;
+use kromer::service::{Context, Router, Services};
 
 use twilight_gateway::cluster::{Cluster, ShardScheme};
 use twilight_http::Client;
-use twilight_model::application::command::{
-    permissions::{CommandPermissions, CommandPermissionsType},
-    BaseCommandOptionData, CommandOption, OptionsCommandOptionData,
-};
 use twilight_model::gateway::Intents;
-use twilight_model::id::GuildId;
+use twilight_model::id::{GuildId, RoleId, UserId};
 
 use anyhow::{anyhow, Result};
 use log::LevelFilter;
+use sqlx::PgPool;
 use tokio::runtime::Runtime;
+use tokio::sync::oneshot;
 
 use ansi_term::{Color, Style};
 
@@ -37,6 +44,37 @@ struct Opt {
     #[structopt(long)]
     /// do not print with pretty colors
     no_color: bool,
+    #[structopt(short, long, parse(from_os_str))]
+    /// the config file to load environment variables from, instead of .env
+    config: Option<PathBuf>,
+    #[structopt(long, parse(from_os_str))]
+    /// a TOML file listing the `users` and `roles` that may use admin commands
+    /// in every server
+    owners: Option<PathBuf>,
+    #[structopt(long = "admin-user", number_of_values = 1)]
+    /// a user that may use admin commands in every server, in addition to
+    /// KROMER_ADMIN_USERS
+    admin_users: Vec<u64>,
+    #[structopt(long = "admin-role", number_of_values = 1)]
+    /// a role that may use admin commands, in addition to KROMER_ADMIN_ROLES
+    admin_roles: Vec<u64>,
+}
+
+impl Opt {
+    /// Gets the owners from the environment, the owners file and the command
+    /// line.
+    fn owners(&self) -> Result<Owners> {
+        let mut owners = config::permissions::owners_from_env()?;
+
+        if let Some(path) = &self.owners {
+            owners.extend(config::permissions::owners_from_file(path)?);
+        }
+
+        owners.extend(self.admin_users.iter().map(|&id| Owner::User(UserId(id))));
+        owners.extend(self.admin_roles.iter().map(|&id| Owner::Role(RoleId(id))));
+
+        Ok(Owners::new(owners))
+    }
 }
 
 #[derive(StructOpt)]
@@ -65,11 +103,23 @@ struct Migrate {
     #[allow(dead_code)]
     /// whether to apply the migrations globally or not
     global: bool,
+    #[structopt(long)]
+    /// only print the changes that would be made
+    dry_run: bool,
 }
 
 fn main() {
+    let opt = Kromer::from_args();
+
     // initialize logging and environment
-    dotenv::dotenv().ok();
+    let env = match &opt.options.config {
+        Some(path) => dotenv::from_path(path).map_err(|err| (path.clone(), err)),
+        None => {
+            dotenv::dotenv().ok();
+            Ok(())
+        }
+    };
+
     env_logger::Builder::new()
         .filter(None, LevelFilter::Info)
         // sqlx query logs are annoying and large
@@ -77,7 +127,10 @@ fn main() {
         .parse_env("KROMER_LOG")
         .init();
 
-    let opt = Kromer::from_args();
+    if let Err((path, err)) = env {
+        error!("failed to load config file {}: {}", path.display(), err);
+        std::process::exit(1);
+    }
 
     let res = match opt.command.unwrap_or_default() {
         Command::Run(run) => Runtime::new().unwrap().block_on(main_run(opt.options, run)),
@@ -92,32 +145,12 @@ fn main() {
     }
 }
 
-async fn main_run(_options: Opt, _run: Run) -> Result<()> {
+async fn main_run(options: Opt, _run: Run) -> Result<()> {
     // get config
     let token = get_discord_token()?;
-    let database = get_database_url()?;
+    let owners = options.owners()?;
 
-    info!("initiating connection to database...");
-
-    // connect to the database
-    let db = match sqlx::PgPool::connect(&database).await {
-        Ok(db) => db,
-        Err(err) => {
-            error!("failed to initiate connection with database");
-            error!("make sure the url provided in DATABASE_URL is correct");
-
-            return Err(err.into());
-        }
-    };
-
-    info!("running database migrations...");
-
-    // run migrations
-    if let Err(err) = kromer::model::migrate(&db).await {
-        error!("failed to run migrations for database");
-
-        return Err(err.into());
-    }
+    let db = connect_database().await?;
 
     // get an http client
     let client = create_client(&token).await?;
@@ -127,7 +160,10 @@ async fn main_run(_options: Opt, _run: Run) -> Result<()> {
     // throw up a cluster
     let cluster = Cluster::builder(
         token,
-        Intents::GUILD_MESSAGES | Intents::GUILD_MESSAGE_REACTIONS,
+        Intents::GUILDS
+            | Intents::GUILD_EMOJIS
+            | Intents::GUILD_MESSAGES
+            | Intents::GUILD_MESSAGE_REACTIONS,
     )
     .shard_scheme(ShardScheme::Auto)
     .build()
@@ -151,17 +187,40 @@ async fn main_run(_options: Opt, _run: Run) -> Result<()> {
         cluster_spawn.up().await;
     });
 
+    // the xp service is shared with the commands that configure and reset it
+    let xp = Xp::default();
+    let resets = PendingResets::default();
+
+    // route our interactions
+    let commands = bot::commands(&owners, &xp, &resets);
+    let router = Router::new()
+        .commands(&commands)
+        .component("top", bot::xp::TopButtons)
+        .component("rrremove", bot::roles::reaction::RemoveReactionRoleMenu)
+        .component("rolemenu", bot::roles::menu::RoleMenuComponents)
+        .component("xpreset", admin::ResetGuildButtons(resets, xp.clone()));
+
+    // xp is saved and decayed in the background
+    let cx = Context::new(client.clone(), db.clone());
+    let flusher = xp.spawn_flusher(cx.clone());
+    let decayer = Xp::spawn_decayer(cx.clone());
+
     // create our services
-    let services = Services::new(Context::new(client.clone(), db.clone()))
-        .add::<bot::xp::Xp>()
-        .add::<bot::xp::RankCommand>()
-        .add::<bot::xp::TopCommand>()
+    let services = Services::new(cx.clone())
+        .with(xp.clone())
+        .with(owners)
         .add::<bot::roles::reaction::ReactionRoles>()
-        .add::<bot::roles::reaction::CreateReactionRole>()
-        .add::<bot::info::InfoCommand>();
+        .with(router);
 
     // spawn our event listeners in another task
-    tokio::spawn(async move { services.run(events).await });
+    let (stop_events, events_stopped) = oneshot::channel::<()>();
+    let events = tokio::spawn(async move {
+        services
+            .run(events, async {
+                let _ = events_stopped.await;
+            })
+            .await
+    });
 
     info!("bot is initialized! waiting for events...");
 
@@ -174,6 +233,18 @@ async fn main_run(_options: Opt, _run: Run) -> Result<()> {
     // do shutdown
     cluster.down();
 
+    // nothing may be awarded after the last flush
+    let _ = stop_events.send(());
+
+    if let Err(err) = events.await {
+        error!("event listeners failed: {:?}", err);
+    }
+
+    info!("saving xp...");
+
+    decayer.abort();
+    flusher.stop().await;
+
     info!("waiting for threads to exit...");
 
     Ok(())
@@ -199,120 +270,176 @@ async fn main_migrate(options: Opt, migrate: Migrate) -> Result<()> {
     // get an http client
     let client = create_client(&token).await?;
 
-    info!("migrating {}...", highlight.paint("/rank"));
-
-    if let Some(guild_id) = guild_id {
-        client
-            .new_create_guild_command(guild_id, "rank")?
-            .chat_input("returns your or another user's level and KR balance")?
-            .command_options(&[CommandOption::User(BaseCommandOptionData {
-                name: String::from("user"),
-                description: String::from("the user to check"),
-                required: false,
-            })])?
-            .exec()
-            .await?;
+    // find out what is already registered
+    let registered = if let Some(guild_id) = guild_id {
+        client.get_guild_commands(guild_id)?.exec().await?
     } else {
-        client
-            .new_create_global_command("rank")?
-            .chat_input("returns your or another user's level and KR balance")?
-            .command_options(&[CommandOption::User(BaseCommandOptionData {
-                name: String::from("user"),
-                description: String::from("the user to check"),
-                required: false,
-            })])?
-            .exec()
-            .await?;
+        client.get_global_commands()?.exec().await?
     }
+    .model()
+    .await?;
+
+    // the state is only needed to route the commands
+    let manifest = bot::commands(
+        &Owners::default(),
+        &Xp::default(),
+        &PendingResets::default(),
+    );
+    let plan = Plan::new(&manifest, &registered);
 
-    info!("migrating {}...", highlight.paint("/top"));
+    print_plan(&options, &plan);
 
-    if let Some(guild_id) = guild_id {
-        client
-            .new_create_guild_command(guild_id, "top")?
-            .chat_input("returns the leading 10 members of the guild in KR balance")?
-            .exec()
-            .await?;
+    if migrate.dry_run {
+        info!("dry run, not applying any changes");
+
+        return Ok(());
+    }
+
+    if plan.is_empty() {
+        info!("commands are already up to date");
     } else {
-        client
-            .new_create_global_command("top")?
-            .chat_input("returns the leading 10 members of the guild in KR balance")?
-            .exec()
-            .await?;
+        // bulk overwrite keeps the ids of commands that already exist
+        let commands = manifest
+            .iter()
+            .map(CommandSpec::to_command)
+            .collect::<Vec<_>>();
+
+        info!(
+            "applying {} command changes...",
+            highlight.paint(
+                plan.changes()
+                    .iter()
+                    .filter(|c| c.is_change())
+                    .count()
+                    .to_string()
+            )
+        );
+
+        if let Some(guild_id) = guild_id {
+            client
+                .set_guild_commands(guild_id, &commands)?
+                .exec()
+                .await?;
+        } else {
+            client.set_global_commands(&commands)?.exec().await?;
+        }
     }
 
-    info!("migrating {}...", highlight.paint("/info"));
+    // give the owners access to the admin commands
+    let owners = options.owners()?;
+    let cx = Context::new(client.clone(), connect_database().await?);
 
-    if let Some(guild_id) = guild_id {
-        client
-            .new_create_guild_command(guild_id, "info")?
-            .chat_input("returns info about the bot currently running")?
-            .exec()
-            .await?;
-    } else {
-        client
-            .new_create_global_command("info")?
-            .chat_input("returns info about the bot currently running")?
-            .exec()
-            .await?;
+    let guild_ids = match guild_id {
+        Some(guild_id) => vec![guild_id],
+        None => current_user_guilds(&client).await?,
+    };
+
+    let mut failed = 0;
+
+    for guild_id in guild_ids {
+        info!(
+            "setting up permissions for guild {}...",
+            highlight.paint(guild_id.to_string())
+        );
+
+        let res = match owners.get(cx.db(), guild_id).await {
+            Ok(owners) => config::permissions::apply(cx.http(), guild_id, &owners).await,
+            Err(err) => Err(err),
+        };
+
+        // one guild shouldn't stop the others from being set up
+        if let Err(err) = res {
+            error!(
+                "failed to set up permissions for guild {}: {}",
+                guild_id, err
+            );
+            failed += 1;
+        }
     }
 
-    info!("migrating {}...", highlight.paint("/reactionroles"));
-
-    if let Some(guild_id) = guild_id {
-        client
-            .new_create_guild_command(guild_id, "reactionroles")?
-            .chat_input("configure reaction roles")?
-            .default_permission(false)
-            .command_options(&[CommandOption::SubCommand(OptionsCommandOptionData {
-                name: String::from("add"),
-                description: String::from("creates a new reaction role"),
-                options: vec![CommandOption::Role(BaseCommandOptionData {
-                    name: String::from("role"),
-                    description: String::from("the role to set the reaction role as"),
-                    required: true,
-                })],
-                required: false,
-            })])?
-            .exec()
-            .await?;
+    if failed > 0 {
+        warn!("migrations complete, but {} guilds failed", failed);
     } else {
-        error!("todo");
+        info!("migrations complete!");
     }
 
-    if let Some(guild_id) = guild_id {
-        info!("setting up permissions");
+    Ok(())
+}
+
+/// Gets the id of every guild the bot is in.
+async fn current_user_guilds(client: &Client) -> Result<Vec<GuildId>> {
+    // the most guilds discord returns at once
+    const LIMIT: u64 = 200;
 
-        let commands = client
-            .get_guild_commands(guild_id)?
-            .exec()
-            .await?
-            .model()
-            .await?;
+    let mut guild_ids = Vec::new();
 
-        let reactionroles_cmd = commands
-            .iter()
-            .find(|cmd| cmd.name == "reactionroles")
-            .unwrap();
-
-        client
-            .set_command_permissions(
-                guild_id,
-                &[(
-                    reactionroles_cmd.id.unwrap(),
-                    CommandPermissions {
-                        id: CommandPermissionsType::User(155785208556290048.into()),
-                        permission: true,
-                    },
-                )],
-            )?
-            .exec()
-            .await?;
+    loop {
+        let request = client.current_user_guilds().limit(LIMIT)?;
+        let request = match guild_ids.last() {
+            Some(&last) => request.after(last),
+            None => request,
+        };
+
+        let page = request.exec().await?.model().await?;
+        let done = (page.len() as u64) < LIMIT;
+
+        guild_ids.extend(page.into_iter().map(|guild| guild.id));
+
+        if done {
+            return Ok(guild_ids);
+        }
     }
+}
+
+fn print_plan(options: &Opt, plan: &Plan) {
+    let style = |style| {
+        if options.no_color {
+            Style::default()
+        } else {
+            style
+        }
+    };
 
-    info!("migrations complete!");
+    info!("command migration plan:");
 
-    Ok(())
+    for change in plan.changes() {
+        let (verb, style) = match change {
+            Change::Create(_) => ("create", style(Color::Green.bold())),
+            Change::Update(..) => ("update", style(Color::Yellow.bold())),
+            Change::Delete(_) => ("delete", style(Color::Red.bold())),
+            Change::Unchanged(_) => ("keep  ", style(Style::new().dimmed())),
+        };
+
+        info!("  {} /{}", style.paint(verb), change.name());
+    }
+}
+
+async fn connect_database() -> Result<PgPool> {
+    let database = get_database_url()?;
+
+    info!("initiating connection to database...");
+
+    // connect to the database
+    let db = match PgPool::connect(&database).await {
+        Ok(db) => db,
+        Err(err) => {
+            error!("failed to initiate connection with database");
+            error!("make sure the url provided in DATABASE_URL is correct");
+
+            return Err(err.into());
+        }
+    };
+
+    info!("running database migrations...");
+
+    // run migrations
+    if let Err(err) = kromer::model::migrate(&db).await {
+        error!("failed to run migrations for database");
+
+        return Err(err.into());
+    }
+
+    Ok(db)
 }
 
 fn get_database_url() -> Result<String> {
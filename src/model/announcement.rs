@@ -0,0 +1,161 @@
+//! Level-up announcements.
+
+use super::Error;
+
+use sqlx::{postgres::Postgres, Executor, FromRow};
+
+use twilight_model::id::{ChannelId, GuildId, UserId};
+
+/// Where level-up announcements are posted.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Target {
+    /// The channel the user leveled up in.
+    #[default]
+    Same,
+    /// A specific channel.
+    Channel(ChannelId),
+    /// The user's DMs.
+    Dm,
+    /// Nowhere; announcements are disabled.
+    None,
+}
+
+/// A guild's level-up announcement settings.
+#[derive(Clone, Debug, Default)]
+pub struct Announcement {
+    target: Target,
+    template: Option<String>,
+}
+
+impl Announcement {
+    /// The template used when a guild hasn't set one.
+    pub const DEFAULT_TEMPLATE: &'static str = "{user} is now level {level} with {kr}KR!";
+
+    /// Where announcements are posted.
+    pub fn target(&self) -> Target {
+        self.target
+    }
+
+    /// The announcement template.
+    ///
+    /// `{user}`, `{level}` and `{kr}` are replaced with the user's mention,
+    /// their new level and their score.
+    pub fn template(&self) -> &str {
+        self.template.as_deref().unwrap_or(Self::DEFAULT_TEMPLATE)
+    }
+
+    /// Fills in the template for a user.
+    pub fn render(&self, user_id: UserId, level: i32, score: i32) -> String {
+        render(self.template(), user_id, level, score)
+    }
+}
+
+/// Fills in an announcement template for a user.
+///
+/// See [`Announcement::template`].
+pub fn render(template: &str, user_id: UserId, level: i32, score: i32) -> String {
+    template
+        .replace("{user}", &format!("<@{}>", user_id))
+        .replace("{level}", &level.to_string())
+        .replace("{kr}", &score.to_string())
+}
+
+#[derive(FromRow)]
+struct Row {
+    target: String,
+    channel_id: Option<i64>,
+    template: Option<String>,
+}
+
+impl From<Row> for Announcement {
+    fn from(row: Row) -> Announcement {
+        let target = match (row.target.as_str(), row.channel_id) {
+            ("channel", Some(channel_id)) => Target::Channel(ChannelId(channel_id as u64)),
+            ("dm", _) => Target::Dm,
+            ("none", _) => Target::None,
+            _ => Target::Same,
+        };
+
+        Announcement {
+            target,
+            template: row.template,
+        }
+    }
+}
+
+/// The announcement settings of a certain guild.
+pub struct Guild(i64);
+
+impl Guild {
+    /// Create a new `Guild` reference.
+    ///
+    /// This does nothing until operations are made to it.
+    pub fn new(id: GuildId) -> Guild {
+        Guild(id.0 as i64)
+    }
+
+    /// Gets the announcement settings of the guild.
+    pub async fn announcement<'a, E>(&self, ex: E) -> Result<Announcement, Error>
+    where
+        E: Executor<'a, Database = Postgres>,
+    {
+        sqlx::query_as::<_, Row>(
+            r#"
+            SELECT target, channel_id, template FROM level_announcements
+            WHERE guild_id = $1
+            "#,
+        )
+        .bind(self.0)
+        .fetch_optional(ex)
+        .await
+        .map(|row| row.map(Announcement::from).unwrap_or_default())
+    }
+
+    /// Sets where announcements are posted.
+    pub async fn set_target<'a, E>(&self, ex: E, target: Target) -> Result<(), Error>
+    where
+        E: Executor<'a, Database = Postgres>,
+    {
+        let (kind, channel_id) = match target {
+            Target::Same => ("same", None),
+            Target::Channel(channel_id) => ("channel", Some(channel_id.0 as i64)),
+            Target::Dm => ("dm", None),
+            Target::None => ("none", None),
+        };
+
+        sqlx::query(
+            r#"
+            INSERT INTO level_announcements (guild_id, target, channel_id)
+            VALUES ($1, $2, $3)
+            ON CONFLICT (guild_id) DO UPDATE
+            SET target = $2, channel_id = $3
+            "#,
+        )
+        .bind(self.0)
+        .bind(kind)
+        .bind(channel_id)
+        .execute(ex)
+        .await
+        .map(|_| ())
+    }
+
+    /// Sets the announcement template, or resets it if `None` is passed.
+    pub async fn set_template<'a, E>(&self, ex: E, template: Option<&str>) -> Result<(), Error>
+    where
+        E: Executor<'a, Database = Postgres>,
+    {
+        sqlx::query(
+            r#"
+            INSERT INTO level_announcements (guild_id, template)
+            VALUES ($1, $2)
+            ON CONFLICT (guild_id) DO UPDATE
+            SET template = $2
+            "#,
+        )
+        .bind(self.0)
+        .bind(template)
+        .execute(ex)
+        .await
+        .map(|_| ())
+    }
+}
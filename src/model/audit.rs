@@ -0,0 +1,84 @@
+//! A log of changes admins make to experience.
+
+use super::Error;
+
+use sqlx::{postgres::Postgres, Executor};
+
+use twilight_model::id::{GuildId, UserId};
+
+/// A change made by an admin.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Action {
+    /// Gave a user experience.
+    Give(i32),
+    /// Took experience from a user.
+    Take(i32),
+    /// Set the experience of a user.
+    Set(i32),
+    /// Reset the experience of a user.
+    ResetUser,
+    /// Reset the experience of everyone in the guild.
+    ResetGuild,
+}
+
+impl Action {
+    fn name(&self) -> &'static str {
+        match self {
+            Action::Give(_) => "give",
+            Action::Take(_) => "take",
+            Action::Set(_) => "set",
+            Action::ResetUser => "reset user",
+            Action::ResetGuild => "reset guild",
+        }
+    }
+
+    fn amount(&self) -> Option<i32> {
+        match *self {
+            Action::Give(amount) | Action::Take(amount) | Action::Set(amount) => Some(amount),
+            Action::ResetUser | Action::ResetGuild => None,
+        }
+    }
+}
+
+/// The audit log of a certain guild.
+pub struct Guild(i64);
+
+impl Guild {
+    /// Create a new `Guild` reference.
+    ///
+    /// This does nothing until operations are made to it.
+    pub fn new(id: GuildId) -> Guild {
+        Guild(id.0 as i64)
+    }
+
+    /// Records a change.
+    ///
+    /// `user_id` should be `None` for [`Action::ResetGuild`].
+    pub async fn log<'a, E>(
+        &self,
+        ex: E,
+        moderator_id: UserId,
+        user_id: Option<UserId>,
+        action: Action,
+        reason: Option<&str>,
+    ) -> Result<(), Error>
+    where
+        E: Executor<'a, Database = Postgres>,
+    {
+        sqlx::query(
+            r#"
+            INSERT INTO xp_audit_log (guild_id, moderator_id, user_id, action, amount, reason)
+            VALUES ($1, $2, $3, $4, $5, $6)
+            "#,
+        )
+        .bind(self.0)
+        .bind(moderator_id.0 as i64)
+        .bind(user_id.map(|id| id.0 as i64))
+        .bind(action.name())
+        .bind(action.amount())
+        .bind(reason)
+        .execute(ex)
+        .await
+        .map(|_| ())
+    }
+}
@@ -0,0 +1,161 @@
+//! Experience decay for inactive users.
+
+use super::Error;
+
+use sqlx::{postgres::Postgres, Executor, FromRow};
+
+use twilight_model::id::GuildId;
+
+use std::fmt::{self, Display, Formatter};
+
+/// How a guild's inactive users lose experience.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, FromRow)]
+pub struct Policy {
+    percent: i32,
+    after_days: i32,
+    floor: i32,
+}
+
+impl Policy {
+    /// Create a new `Policy`.
+    pub fn new(percent: i32, after_days: i32, floor: i32) -> Policy {
+        Policy {
+            percent,
+            after_days,
+            floor,
+        }
+    }
+
+    /// How much experience inactive users lose every week, out of 100.
+    pub fn percent(&self) -> i32 {
+        self.percent
+    }
+
+    /// How many days users have to be inactive before they start losing
+    /// experience.
+    pub fn after_days(&self) -> i32 {
+        self.after_days
+    }
+
+    /// The experience users never decay below.
+    pub fn floor(&self) -> i32 {
+        self.floor
+    }
+}
+
+impl Display for Policy {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(
+            f,
+            "members lose {}% of their KR every week after {} days without \
+             talking, down to {}KR",
+            self.percent, self.after_days, self.floor,
+        )
+    }
+}
+
+/// The decay policy of a certain guild.
+pub struct Guild(i64);
+
+impl Guild {
+    /// Create a new `Guild` reference.
+    ///
+    /// This does nothing until operations are made to it.
+    pub fn new(id: GuildId) -> Guild {
+        Guild(id.0 as i64)
+    }
+
+    /// Gets the decay policy of the guild, or `None` if it doesn't have one.
+    pub async fn policy<'a, E>(&self, ex: E) -> Result<Option<Policy>, Error>
+    where
+        E: Executor<'a, Database = Postgres>,
+    {
+        sqlx::query_as("SELECT percent, after_days, floor FROM xp_decay WHERE guild_id = $1")
+            .bind(self.0)
+            .fetch_optional(ex)
+            .await
+    }
+
+    /// Sets the decay policy of the guild.
+    pub async fn set_policy<'a, E>(&self, ex: E, policy: Policy) -> Result<(), Error>
+    where
+        E: Executor<'a, Database = Postgres>,
+    {
+        sqlx::query(
+            r#"
+            INSERT INTO xp_decay (guild_id, percent, after_days, floor)
+            VALUES ($1, $2, $3, $4)
+            ON CONFLICT (guild_id) DO UPDATE
+            SET percent = $2, after_days = $3, floor = $4
+            "#,
+        )
+        .bind(self.0)
+        .bind(policy.percent)
+        .bind(policy.after_days)
+        .bind(policy.floor)
+        .execute(ex)
+        .await
+        .map(|_| ())
+    }
+
+    /// Removes the decay policy of the guild.
+    ///
+    /// Returns `false` if it didn't have one.
+    pub async fn remove_policy<'a, E>(&self, ex: E) -> Result<bool, Error>
+    where
+        E: Executor<'a, Database = Postgres>,
+    {
+        sqlx::query("DELETE FROM xp_decay WHERE guild_id = $1")
+            .bind(self.0)
+            .execute(ex)
+            .await
+            .map(|res| res.rows_affected() > 0)
+    }
+}
+
+/// Applies the decay policy of every guild.
+///
+/// Users that haven't been awarded experience for a message in the policy's
+/// number of days lose a percentage of their experience, at most once a week.
+/// Users that were never awarded for a message count as inactive. Everything
+/// lost is recorded in the ledger as [`Source::Decay`].
+///
+/// Returns how many users lost experience.
+///
+/// [`Source::Decay`]: super::xp::Source::Decay
+pub async fn decay<'a, E>(ex: E) -> Result<u64, Error>
+where
+    E: Executor<'a, Database = Postgres>,
+{
+    sqlx::query(
+        r#"
+        WITH decayed AS (
+            UPDATE xp SET score = d.score, last_decayed_at = now()
+            FROM (
+                SELECT
+                    xp.guild_id,
+                    xp.user_id,
+                    xp.score AS old_score,
+                    GREATEST(
+                        p.floor,
+                        xp.score - CEIL(xp.score::BIGINT * p.percent / 100.0)::INTEGER
+                    ) AS score
+                FROM xp JOIN xp_decay p USING (guild_id)
+                WHERE xp.score > p.floor
+                    AND COALESCE(xp.last_awarded_at, '-infinity')
+                        < now() - make_interval(days => p.after_days)
+                    AND COALESCE(xp.last_decayed_at, '-infinity')
+                        <= now() - INTERVAL '7 days'
+            ) d
+            WHERE xp.guild_id = d.guild_id AND xp.user_id = d.user_id
+            RETURNING xp.guild_id, xp.user_id, d.score - d.old_score AS amount
+        )
+        INSERT INTO xp_ledger (guild_id, user_id, amount, source)
+        SELECT guild_id, user_id, amount, 'decay' FROM decayed
+        WHERE amount <> 0
+        "#,
+    )
+    .execute(ex)
+    .await
+    .map(|res| res.rows_affected())
+}
@@ -0,0 +1,444 @@
+//! Filters that stop spam from giving experience.
+
+use super::Error;
+
+use sqlx::{postgres::Postgres, Executor, FromRow};
+
+use twilight_model::id::GuildId;
+
+use std::collections::HashSet;
+
+/// A guild's spam filters.
+///
+/// By default, nothing is filtered.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, FromRow)]
+pub struct Filters {
+    min_length: i32,
+    duplicates: bool,
+    emoji_only: bool,
+    hourly_cap: Option<i32>,
+}
+
+impl Filters {
+    /// Messages shorter than this many characters don't give experience.
+    ///
+    /// Only letters, numbers and spaces are counted; see [`normalize`].
+    pub fn min_length(&self) -> i32 {
+        self.min_length
+    }
+
+    /// Whether messages similar to the user's recent messages are ignored.
+    pub fn duplicates(&self) -> bool {
+        self.duplicates
+    }
+
+    /// Whether messages that are only emoji or attachments are ignored.
+    pub fn emoji_only(&self) -> bool {
+        self.emoji_only
+    }
+
+    /// The most experience a user can get from messages in an hour.
+    ///
+    /// This is enforced by [`award`](super::xp::award).
+    pub fn hourly_cap(&self) -> Option<i32> {
+        self.hourly_cap
+    }
+
+    /// Checks if a message should give experience.
+    ///
+    /// `recent` are the [`normalize`]d contents of the user's recent
+    /// messages.
+    pub fn allows<'a>(&self, content: &str, recent: impl IntoIterator<Item = &'a str>) -> bool {
+        if self.emoji_only && is_emoji_only(content) {
+            return false;
+        }
+
+        let normalized = normalize(content);
+
+        if (normalized.chars().count() as i32) < self.min_length {
+            return false;
+        }
+
+        if self.duplicates
+            && recent
+                .into_iter()
+                .any(|other| is_similar(&normalized, other))
+        {
+            return false;
+        }
+
+        true
+    }
+}
+
+/// Strips a message down to lowercase letters and numbers, with single spaces
+/// between words.
+///
+/// This makes messages that only differ in case or punctuation the same.
+pub fn normalize(content: &str) -> String {
+    let mut normalized = String::with_capacity(content.len());
+
+    for word in content.split_whitespace() {
+        let word = word
+            .chars()
+            .filter(|ch| ch.is_alphanumeric())
+            .flat_map(char::to_lowercase)
+            .collect::<String>();
+
+        // skip words that were only punctuation
+        if word.is_empty() {
+            continue;
+        }
+
+        if !normalized.is_empty() {
+            normalized.push(' ');
+        }
+
+        normalized.push_str(&word);
+    }
+
+    normalized
+}
+
+/// Checks if two [`normalize`]d messages are the same or nearly the same.
+///
+/// Messages are compared by the pairs of characters they share, so small
+/// edits like adding a letter or swapping a word are still caught.
+pub fn is_similar(a: &str, b: &str) -> bool {
+    /// How much of the messages have to be shared, from 0 to 1.
+    const THRESHOLD: f64 = 0.8;
+
+    if a == b {
+        return true;
+    }
+
+    let bigrams = |s: &str| {
+        let chars = s.chars().collect::<Vec<_>>();
+
+        chars
+            .windows(2)
+            .map(|w| (w[0], w[1]))
+            .collect::<HashSet<_>>()
+    };
+
+    let (a, b) = (bigrams(a), bigrams(b));
+
+    if a.is_empty() || b.is_empty() {
+        // too short to compare, and they aren't equal
+        return false;
+    }
+
+    let shared = a.intersection(&b).count();
+
+    2. * shared as f64 / (a.len() + b.len()) as f64 >= THRESHOLD
+}
+
+/// Checks if a message has no text, only custom or unicode emoji.
+///
+/// Messages with only attachments or stickers have no content, so they count
+/// too.
+pub fn is_emoji_only(content: &str) -> bool {
+    let mut rest = content;
+
+    while let Some(ch) = rest.chars().next() {
+        if ch == '<' {
+            if let Some(len) = custom_emoji_len(rest) {
+                rest = &rest[len..];
+                continue;
+            }
+        }
+
+        // keycaps start with a digit, '#' or '*'
+        if let Some(len) = keycap_len(rest) {
+            rest = &rest[len..];
+            continue;
+        }
+
+        if !(ch.is_whitespace() || is_emoji_char(ch)) {
+            return false;
+        }
+
+        rest = &rest[ch.len_utf8()..];
+    }
+
+    true
+}
+
+/// Gets the length of a custom emoji like `<:name:id>` or `<a:name:id>` at
+/// the start of `s`.
+fn custom_emoji_len(s: &str) -> Option<usize> {
+    let end = s.find('>')?;
+    let inner = &s[1..end];
+
+    let inner = inner.strip_prefix('a').unwrap_or(inner);
+    let inner = inner.strip_prefix(':')?;
+    let (name, id) = inner.split_once(':')?;
+
+    let valid = !name.is_empty()
+        && name.chars().all(|ch| ch.is_alphanumeric() || ch == '_')
+        && !id.is_empty()
+        && id.chars().all(|ch| ch.is_ascii_digit());
+
+    valid.then(|| end + 1)
+}
+
+/// Gets the length of a keycap emoji like `1️⃣` at the start of `s`.
+fn keycap_len(s: &str) -> Option<usize> {
+    let mut chars = s.char_indices();
+
+    match chars.next()? {
+        (_, '0'..='9' | '#' | '*') => (),
+        _ => return None,
+    }
+
+    // the variation selector is optional
+    match chars.next()? {
+        (_, '\u{FE0F}') => match chars.next()? {
+            (i, '\u{20E3}') => Some(i + '\u{20E3}'.len_utf8()),
+            _ => None,
+        },
+        (i, '\u{20E3}') => Some(i + '\u{20E3}'.len_utf8()),
+        _ => None,
+    }
+}
+
+/// Checks if a character is part of a unicode emoji.
+///
+/// This is an approximation that covers the emoji blocks, along with the
+/// joiners and modifiers used to build emoji sequences.
+fn is_emoji_char(ch: char) -> bool {
+    matches!(
+        ch as u32,
+        // arrows, misc technical, misc symbols and dingbats
+        0x2190..=0x21FF
+            | 0x2300..=0x23FF
+            | 0x2600..=0x27BF
+            | 0x2B00..=0x2BFF
+            // mahjong tiles up to symbols and pictographs extended-a,
+            // including regional indicators and skin tones
+            | 0x1F000..=0x1FAFF
+            // zero width joiner, keycap and variation selectors
+            | 0x200D
+            | 0x20E3
+            | 0xFE00..=0xFE0F
+            // copyright, registered, trade mark
+            | 0xA9
+            | 0xAE
+            | 0x2122
+    )
+}
+
+/// The spam filters of a certain guild.
+pub struct Guild(i64);
+
+impl Guild {
+    /// Create a new `Guild` reference.
+    ///
+    /// This does nothing until operations are made to it.
+    pub fn new(id: GuildId) -> Guild {
+        Guild(id.0 as i64)
+    }
+
+    /// Gets the spam filters of the guild.
+    pub async fn filters<'a, E>(&self, ex: E) -> Result<Filters, Error>
+    where
+        E: Executor<'a, Database = Postgres>,
+    {
+        sqlx::query_as(
+            r#"
+            SELECT min_length, duplicates, emoji_only, hourly_cap FROM xp_filters
+            WHERE guild_id = $1
+            "#,
+        )
+        .bind(self.0)
+        .fetch_optional(ex)
+        .await
+        .map(Option::unwrap_or_default)
+    }
+
+    /// Sets the shortest message that gives experience.
+    pub async fn set_min_length<'a, E>(&self, ex: E, min_length: i32) -> Result<(), Error>
+    where
+        E: Executor<'a, Database = Postgres>,
+    {
+        sqlx::query(
+            r#"
+            INSERT INTO xp_filters (guild_id, min_length)
+            VALUES ($1, $2)
+            ON CONFLICT (guild_id) DO UPDATE
+            SET min_length = $2
+            "#,
+        )
+        .bind(self.0)
+        .bind(min_length)
+        .execute(ex)
+        .await
+        .map(|_| ())
+    }
+
+    /// Sets whether messages similar to the user's recent messages are
+    /// ignored.
+    pub async fn set_duplicates<'a, E>(&self, ex: E, duplicates: bool) -> Result<(), Error>
+    where
+        E: Executor<'a, Database = Postgres>,
+    {
+        sqlx::query(
+            r#"
+            INSERT INTO xp_filters (guild_id, duplicates)
+            VALUES ($1, $2)
+            ON CONFLICT (guild_id) DO UPDATE
+            SET duplicates = $2
+            "#,
+        )
+        .bind(self.0)
+        .bind(duplicates)
+        .execute(ex)
+        .await
+        .map(|_| ())
+    }
+
+    /// Sets whether messages that are only emoji or attachments are ignored.
+    pub async fn set_emoji_only<'a, E>(&self, ex: E, emoji_only: bool) -> Result<(), Error>
+    where
+        E: Executor<'a, Database = Postgres>,
+    {
+        sqlx::query(
+            r#"
+            INSERT INTO xp_filters (guild_id, emoji_only)
+            VALUES ($1, $2)
+            ON CONFLICT (guild_id) DO UPDATE
+            SET emoji_only = $2
+            "#,
+        )
+        .bind(self.0)
+        .bind(emoji_only)
+        .execute(ex)
+        .await
+        .map(|_| ())
+    }
+
+    /// Sets the most experience a user can get from messages in an hour, or
+    /// removes the limit if `None` is passed.
+    pub async fn set_hourly_cap<'a, E>(&self, ex: E, hourly_cap: Option<i32>) -> Result<(), Error>
+    where
+        E: Executor<'a, Database = Postgres>,
+    {
+        sqlx::query(
+            r#"
+            INSERT INTO xp_filters (guild_id, hourly_cap)
+            VALUES ($1, $2)
+            ON CONFLICT (guild_id) DO UPDATE
+            SET hourly_cap = $2
+            "#,
+        )
+        .bind(self.0)
+        .bind(hourly_cap)
+        .execute(ex)
+        .await
+        .map(|_| ())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{is_emoji_only, is_similar, normalize, Filters};
+
+    #[test]
+    fn normalize_folds_case_and_whitespace() {
+        assert_eq!(normalize("Hello   World"), "hello world");
+        assert_eq!(normalize("  hello\n\tworld  "), "hello world");
+        assert_eq!(normalize("HELLO, world!!!"), "hello world");
+        assert_eq!(normalize("a b c"), "a b c");
+        assert_eq!(normalize("i ... am here"), "i am here");
+        assert_eq!(normalize("ÉCOLE"), "école");
+        assert_eq!(normalize("!!! ???"), "");
+        assert_eq!(normalize(""), "");
+    }
+
+    #[test]
+    fn similar_messages() {
+        assert!(is_similar("hello world", "hello world"));
+        assert!(is_similar(
+            &normalize("Hello, World!"),
+            &normalize("hello world")
+        ));
+        assert!(is_similar("hello world", "hello worlds"));
+        assert!(!is_similar("hello world", "goodbye moon"));
+
+        // too short to compare unless they're equal
+        assert!(is_similar("a", "a"));
+        assert!(!is_similar("a", "b"));
+        assert!(!is_similar("", "hi"));
+    }
+
+    #[test]
+    fn similarity_threshold() {
+        // 4 of 5 and 5 pairs shared, right at the threshold
+        assert!(is_similar("abcdef", "abcdeg"));
+        // 4 of 5 and 6 pairs shared, just under it
+        assert!(!is_similar("abcdef", "abcdexy"));
+    }
+
+    #[test]
+    fn emoji_only() {
+        let emoji_only = [
+            "",
+            "   ",
+            "😀",
+            "😀 😀😀",
+            "<:kromer:896364389612601344>",
+            "<a:spin:896364389612601344> <:kromer:896364389612601344>",
+            "😀 <:kromer:896364389612601344>",
+            // sequences
+            "❤️",
+            "🇺🇸🇯🇵",
+            "👍🏽",
+            "👨‍👩‍👧‍👦",
+            "🏳️‍🌈",
+            "1️⃣ #️⃣ *️⃣",
+        ];
+
+        for content in emoji_only {
+            assert!(is_emoji_only(content), "{:?}", content);
+        }
+
+        let not_emoji_only = [
+            "hi",
+            "hi 😀",
+            "😀 hi",
+            "1",
+            "1 😀",
+            "<:kromer:>",
+            "<:kromer:abc>",
+            "<kromer:896364389612601344>",
+            "<@896364389612601344>",
+            "<:kromer:896364389612601344",
+        ];
+
+        for content in not_emoji_only {
+            assert!(!is_emoji_only(content), "{:?}", content);
+        }
+    }
+
+    #[test]
+    fn filters_allow() {
+        let filters = Filters::default();
+
+        assert!(filters.allows("hi", []));
+        assert!(filters.allows("😀", []));
+        assert!(filters.allows("hello world", ["hello world"]));
+
+        let filters = Filters {
+            min_length: 3,
+            duplicates: true,
+            emoji_only: true,
+            hourly_cap: None,
+        };
+
+        assert!(!filters.allows("hi", []));
+        assert!(!filters.allows("h!!!", []));
+        assert!(!filters.allows("😀😀😀", []));
+        assert!(!filters.allows("Hello, World!", ["hello world"]));
+        assert!(filters.allows("hello world", ["goodbye moon"]));
+    }
+}
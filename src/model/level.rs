@@ -0,0 +1,252 @@
+//! How much experience each level takes.
+
+use super::Error;
+
+use sqlx::{postgres::Postgres, Executor, FromRow};
+
+use twilight_model::id::GuildId;
+
+use std::fmt::{self, Display, Formatter};
+
+/// A formula mapping experience to levels.
+///
+/// Levels start at 1, which takes no experience to reach.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Curve {
+    /// Every level takes the same amount of experience.
+    Linear(i32),
+    /// Level `n` takes `factor * (n - 1)^2` experience.
+    Quadratic(i32),
+    /// Going from level `n` to `n + 1` takes `5n^2 + 50n + 100` experience,
+    /// counting levels from 0 like MEE6 does.
+    Mee6,
+    /// The experience needed for level 2, 3, 4 and so on. There are no levels
+    /// past the last threshold.
+    Custom(Vec<i32>),
+}
+
+impl Curve {
+    /// Calculates the level for a given score.
+    pub fn level(&self, score: i32) -> i32 {
+        // every curve only ever goes up, so we can search for the level
+        let (mut lo, mut hi) = (1, i32::MAX);
+
+        while lo < hi {
+            let mid = lo + (hi - lo + 1) / 2;
+
+            match self.score_for(mid) {
+                Some(needed) if needed <= score => lo = mid,
+                _ => hi = mid - 1,
+            }
+        }
+
+        lo
+    }
+
+    /// Calculates the score needed to reach a level.
+    ///
+    /// This is the inverse of [`Curve::level`]. Returns `None` if the level
+    /// can't be reached.
+    pub fn score_for(&self, level: i32) -> Option<i32> {
+        let n = (level - 1).max(0) as i64;
+
+        // levels can get high enough to overflow, which just means they can't
+        // be reached
+        let score = match self {
+            Curve::Linear(step) => (*step as i64).checked_mul(n)?,
+            Curve::Quadratic(factor) => (*factor as i64).checked_mul(n)?.checked_mul(n)?,
+            Curve::Mee6 => {
+                // the sum of the squares below n
+                let squares = (n - 1).checked_mul(n)?.checked_mul(2 * n - 1)? / 6;
+                let linear = n.checked_mul(n - 1)?.checked_mul(25)?;
+
+                (5 * squares).checked_add(linear)?.checked_add(100 * n)?
+            }
+            Curve::Custom(thresholds) => match n {
+                0 => 0,
+                n => *thresholds.get(n as usize - 1)? as i64,
+            },
+        };
+
+        i32::try_from(score).ok()
+    }
+
+    /// Checks that the curve only ever goes up.
+    pub fn is_valid(&self) -> bool {
+        match self {
+            Curve::Linear(step) => *step > 0,
+            Curve::Quadratic(factor) => *factor > 0,
+            Curve::Mee6 => true,
+            Curve::Custom(thresholds) => {
+                thresholds.first().map(|first| *first > 0).unwrap_or(true)
+                    && thresholds.windows(2).all(|w| w[0] < w[1])
+            }
+        }
+    }
+}
+
+impl Default for Curve {
+    fn default() -> Curve {
+        Curve::Quadratic(30)
+    }
+}
+
+impl Display for Curve {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            Curve::Linear(step) => write!(f, "linear, {}KR per level", step),
+            Curve::Quadratic(factor) => write!(f, "quadratic, {}KR × (level - 1)²", factor),
+            Curve::Mee6 => f.write_str("MEE6"),
+            Curve::Custom(thresholds) => {
+                f.write_str("custom")?;
+
+                for (i, threshold) in thresholds.iter().enumerate() {
+                    let sep = if i > 0 { ',' } else { ':' };
+
+                    write!(f, "{} {}KR", sep, threshold)?;
+                }
+
+                Ok(())
+            }
+        }
+    }
+}
+
+#[derive(FromRow)]
+struct Row {
+    kind: String,
+    factor: i32,
+    thresholds: Vec<i32>,
+}
+
+impl From<Row> for Curve {
+    fn from(row: Row) -> Curve {
+        match row.kind.as_str() {
+            "linear" => Curve::Linear(row.factor),
+            "quadratic" => Curve::Quadratic(row.factor),
+            "mee6" => Curve::Mee6,
+            "custom" => Curve::Custom(row.thresholds),
+            _ => Curve::default(),
+        }
+    }
+}
+
+/// The level curve of a certain guild.
+pub struct Guild(i64);
+
+impl Guild {
+    /// Create a new `Guild` reference.
+    ///
+    /// This does nothing until operations are made to it.
+    pub fn new(id: GuildId) -> Guild {
+        Guild(id.0 as i64)
+    }
+
+    /// Gets the level curve of the guild.
+    ///
+    /// If the guild hasn't set one, this returns [`Curve::default`].
+    pub async fn curve<'a, E>(&self, ex: E) -> Result<Curve, Error>
+    where
+        E: Executor<'a, Database = Postgres>,
+    {
+        sqlx::query_as::<_, Row>(
+            "SELECT kind, factor, thresholds FROM level_curves WHERE guild_id = $1",
+        )
+        .bind(self.0)
+        .fetch_optional(ex)
+        .await
+        .map(|row| row.map(Curve::from).unwrap_or_default())
+    }
+
+    /// Sets the level curve of the guild.
+    pub async fn set_curve<'a, E>(&self, ex: E, curve: &Curve) -> Result<(), Error>
+    where
+        E: Executor<'a, Database = Postgres>,
+    {
+        let (kind, factor, thresholds) = match curve {
+            Curve::Linear(step) => ("linear", *step, &[][..]),
+            Curve::Quadratic(factor) => ("quadratic", *factor, &[][..]),
+            Curve::Mee6 => ("mee6", 0, &[][..]),
+            Curve::Custom(thresholds) => ("custom", 0, thresholds.as_slice()),
+        };
+
+        sqlx::query(
+            r#"
+            INSERT INTO level_curves (guild_id, kind, factor, thresholds)
+            VALUES ($1, $2, $3, $4)
+            ON CONFLICT (guild_id) DO UPDATE
+            SET kind = $2, factor = $3, thresholds = $4
+            "#,
+        )
+        .bind(self.0)
+        .bind(kind)
+        .bind(factor)
+        .bind(thresholds)
+        .execute(ex)
+        .await
+        .map(|_| ())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Curve;
+
+    fn curves() -> Vec<Curve> {
+        vec![
+            Curve::Linear(100),
+            Curve::Quadratic(30),
+            Curve::Quadratic(1),
+            Curve::Mee6,
+            Curve::Custom(vec![100, 250, 600, 1000]),
+        ]
+    }
+
+    #[test]
+    fn round_trip() {
+        for curve in curves() {
+            for level in 1..=10_000 {
+                let score = match curve.score_for(level) {
+                    Some(score) => score,
+                    None => break,
+                };
+
+                assert_eq!(curve.level(score), level, "{:?}", curve);
+
+                if level > 1 {
+                    assert_eq!(curve.level(score - 1), level - 1, "{:?}", curve);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn highest_level() {
+        for curve in curves() {
+            // the search shouldn't go past the last level
+            let level = curve.level(i32::MAX);
+
+            assert!(curve.score_for(level).is_some(), "{:?}", curve);
+            assert_eq!(curve.score_for(level + 1), None, "{:?}", curve);
+        }
+    }
+
+    #[test]
+    fn unreachable_levels() {
+        for curve in curves() {
+            assert_eq!(curve.score_for(i32::MAX), None, "{:?}", curve);
+        }
+    }
+
+    #[test]
+    fn mee6() {
+        let curve = Curve::Mee6;
+
+        // these are the thresholds MEE6 shows
+        assert_eq!(curve.score_for(2), Some(100));
+        assert_eq!(curve.score_for(3), Some(255));
+        assert_eq!(curve.score_for(4), Some(475));
+        assert_eq!(curve.level(0), 1);
+        assert_eq!(curve.level(-5), 1);
+    }
+}
@@ -1,5 +1,12 @@
 //! Bot storage models supported by [`sqlx`].
 
+pub mod announcement;
+pub mod audit;
+pub mod decay;
+pub mod filter;
+pub mod level;
+pub mod multiplier;
+pub mod permissions;
 pub mod roles;
 pub mod xp;
 
@@ -16,27 +23,73 @@ use sqlx::{
 use twilight_model::channel::ReactionType;
 
 use std::fmt::{self, Debug, Display, Formatter};
-use std::mem;
 use std::ops::Deref;
 
 /// Stores emojis in SQL records.
 ///
-/// Because a Discord emoji may also be custom, it doesn't make sense to store
-/// the codepoint as an INTEGER in your SQL database of choice. Instead, we use
-/// a BIGINT and switch between the last bit.
-#[derive(Clone, Copy)]
+/// A unicode emoji may be made of many codepoints (flags, skin tones, keycaps
+/// and ZWJ sequences), so emojis are stored as TEXT. Unicode emojis are stored
+/// as-is, and custom emojis as `custom:` followed by their id. No unicode
+/// emoji starts with a letter, so the two can't be confused.
+#[derive(Clone, PartialEq, Eq)]
 pub enum Emoji {
-    Unicode(char),
+    Unicode(String),
     Custom(u64),
+    /// An emoji stored before emojis were stored as TEXT, in the old BIGINT
+    /// encoding.
+    ///
+    /// That encoding could mix up custom emoji ids, and only kept the first
+    /// codepoint of unicode emojis, so some emojis can't be converted.
+    /// Instead, these are matched with [`Emoji::legacy`] and replaced with the
+    /// real emoji when they are found. Unicode emojis that are complete on
+    /// their own were converted when they were moved over.
+    Legacy(i64),
 }
 
 impl Emoji {
-    const CUSTOM_BIT: usize = mem::size_of::<i64>() - 1;
+    const CUSTOM_PREFIX: &'static str = "custom:";
+    const LEGACY_PREFIX: &'static str = "legacy:";
 
-    fn as_i64(&self) -> i64 {
+    /// The bit the old BIGINT encoding set for custom emojis.
+    ///
+    /// This was meant to be the last bit, but is actually bit 7.
+    const LEGACY_CUSTOM_BIT: usize = 7;
+
+    /// Gets an emoji in the old BIGINT encoding, for finding records that
+    /// haven't been replaced yet.
+    ///
+    /// Unicode emojis were stored as their first codepoint, so every emoji
+    /// starting with the same codepoint (like flags) has the same legacy
+    /// encoding.
+    pub fn legacy(&self) -> Option<Emoji> {
+        match self {
+            Emoji::Unicode(name) => name.chars().next().map(|c| Emoji::Legacy(c as i64)),
+            Emoji::Custom(id) => Some(Emoji::Legacy(*id as i64 | (1 << Self::LEGACY_CUSTOM_BIT))),
+            Emoji::Legacy(data) => Some(Emoji::Legacy(*data)),
+        }
+    }
+
+    /// Gets the emoji as it is stored.
+    pub fn to_text(&self) -> String {
         match self {
-            Emoji::Custom(id) => *id as i64 | (1 << Self::CUSTOM_BIT),
-            Emoji::Unicode(ch) => *ch as i64,
+            Emoji::Unicode(name) => name.clone(),
+            Emoji::Custom(id) => format!("{}{}", Self::CUSTOM_PREFIX, id),
+            Emoji::Legacy(data) => format!("{}{}", Self::LEGACY_PREFIX, data),
+        }
+    }
+
+    /// Parses an emoji as it is stored.
+    ///
+    /// This is the inverse of [`Emoji::to_text`].
+    pub fn from_text(text: &str) -> Option<Emoji> {
+        if let Some(id) = text.strip_prefix(Self::CUSTOM_PREFIX) {
+            id.parse().ok().map(Emoji::Custom)
+        } else if let Some(data) = text.strip_prefix(Self::LEGACY_PREFIX) {
+            data.parse().ok().map(Emoji::Legacy)
+        } else if !text.is_empty() {
+            Some(Emoji::Unicode(String::from(text)))
+        } else {
+            None
         }
     }
 }
@@ -44,11 +97,12 @@ impl Emoji {
 impl Debug for Emoji {
     fn fmt(&self, f: &mut Formatter) -> fmt::Result {
         if f.alternate() {
-            write!(f, "{}", self.as_i64())
+            write!(f, "{}", self.to_text())
         } else {
             match self {
-                Emoji::Unicode(ch) => f.debug_tuple("Emoji::Unicode").field(ch).finish(),
-                Emoji::Custom(id) => f.debug_tuple("Emoji::Unicode").field(id).finish(),
+                Emoji::Unicode(name) => f.debug_tuple("Emoji::Unicode").field(name).finish(),
+                Emoji::Custom(id) => f.debug_tuple("Emoji::Custom").field(id).finish(),
+                Emoji::Legacy(data) => f.debug_tuple("Emoji::Legacy").field(data).finish(),
             }
         }
     }
@@ -57,63 +111,63 @@ impl Debug for Emoji {
 impl Display for Emoji {
     fn fmt(&self, f: &mut Formatter) -> fmt::Result {
         match self {
-            Emoji::Unicode(ch) => write!(f, "{}", ch),
+            Emoji::Unicode(name) => f.write_str(name),
             Emoji::Custom(id) => write!(f, "<:emoji:{}>", id),
+            // the start of a unicode emoji
+            Emoji::Legacy(data) if *data < 0x110000 => match char::from_u32(*data as u32) {
+                Some(c) => write!(f, "{}", c),
+                None => write!(f, "<:emoji:{}>", data),
+            },
+            // our best guess
+            Emoji::Legacy(data) => write!(
+                f,
+                "<:emoji:{}>",
+                *data as u64 & !(1 << Self::LEGACY_CUSTOM_BIT)
+            ),
         }
     }
 }
 
 impl<'r, DB: Database> Decode<'r, DB> for Emoji
 where
-    i64: Decode<'r, DB>,
+    &'r str: Decode<'r, DB>,
 {
     fn decode(
         value: <DB as HasValueRef<'r>>::ValueRef,
     ) -> Result<Emoji, Box<dyn std::error::Error + 'static + Send + Sync>> {
-        let data = i64::decode(value)? as u64;
+        let text = <&str>::decode(value)?;
 
-        // get the custom bit
-        let is_custom = (data >> Self::CUSTOM_BIT) & 1 > 0;
-
-        if is_custom {
-            // return the rest of the data as an emoji id
-            Ok(Emoji::Custom(data & !(1 << Self::CUSTOM_BIT)))
-        } else {
-            // decode it as a u32 codepoint
-            Ok(Emoji::Unicode(
-                char::from_u32(data as u32).ok_or("codepoint invalid")?,
-            ))
-        }
+        Emoji::from_text(text).ok_or_else(|| format!("invalid emoji {:?}", text).into())
     }
 }
 
 impl<'q, DB: Database> Encode<'q, DB> for Emoji
 where
-    i64: Encode<'q, DB>,
+    String: Encode<'q, DB>,
 {
     fn encode_by_ref(&self, buf: &mut <DB as HasArguments<'q>>::ArgumentBuffer) -> IsNull {
-        self.as_i64().encode(buf)
+        self.to_text().encode(buf)
     }
 
     fn produces(&self) -> Option<<DB as Database>::TypeInfo> {
-        self.as_i64().produces()
+        self.to_text().produces()
     }
 
     fn size_hint(&self) -> usize {
-        self.as_i64().size_hint()
+        self.to_text().size_hint()
     }
 }
 
 impl<DB: Database> Type<DB> for Emoji
 where
-    i64: Type<DB>,
+    String: Type<DB>,
 {
     fn type_info() -> <DB as Database>::TypeInfo {
-        i64::type_info()
+        String::type_info()
     }
 
     fn compatible(ty: &<DB as Database>::TypeInfo) -> bool {
-        i64::compatible(ty)
+        String::compatible(ty)
     }
 }
 
@@ -122,10 +176,7 @@ where
 impl From<ReactionType> for Emoji {
     fn from(r: ReactionType) -> Emoji {
         match r {
-            ReactionType::Unicode { name, .. } => {
-                // pull the unicode character out
-                Emoji::Unicode(name.chars().next().unwrap())
-            }
+            ReactionType::Unicode { name, .. } => Emoji::Unicode(name),
             ReactionType::Custom { id, .. } => Emoji::Custom(id.0),
         }
     }
@@ -139,3 +190,121 @@ where
 {
     sqlx::migrate!().run(ex).await
 }
+
+#[cfg(test)]
+mod tests {
+    use super::Emoji;
+
+    use twilight_model::channel::ReactionType;
+    use twilight_model::id::EmojiId;
+
+    fn round_trip(emoji: Emoji) {
+        let text = emoji.to_text();
+
+        assert_eq!(Emoji::from_text(&text), Some(emoji), "{:?}", text);
+    }
+
+    fn unicode(name: &str) -> Emoji {
+        Emoji::from(ReactionType::Unicode {
+            name: String::from(name),
+        })
+    }
+
+    #[test]
+    fn unicode_round_trip() {
+        let emojis = [
+            // a single codepoint
+            "😀",
+            "🚀",
+            // with a variation selector
+            "❤️",
+            "☺️",
+            // flags are two regional indicators
+            "🇺🇸",
+            "🇯🇵",
+            // tag sequences
+            "🏴󠁧󠁢󠁳󠁣󠁴󠁿",
+            // skin tones
+            "👍🏽",
+            "👋🏿",
+            // keycaps start with a digit, '#' or '*'
+            "1️⃣",
+            "#️⃣",
+            "*️⃣",
+            // ZWJ sequences
+            "👨‍👩‍👧‍👦",
+            "🏳️‍🌈",
+            "🧑🏻‍💻",
+        ];
+
+        for name in emojis {
+            let emoji = unicode(name);
+
+            assert_eq!(emoji.to_text(), name);
+            round_trip(emoji);
+        }
+    }
+
+    #[test]
+    fn unicode_does_not_collide() {
+        let pairs = [("🇺🇸", "🇺🇦"), ("👍", "👍🏽"), ("👨", "👨‍👩‍👧‍👦"), ("1️⃣", "2️⃣")];
+
+        for (a, b) in pairs {
+            assert_ne!(unicode(a).to_text(), unicode(b).to_text());
+        }
+    }
+
+    #[test]
+    fn custom_round_trip() {
+        let ids = [
+            1,
+            // bit 7 used to be the custom bit
+            128,
+            0x1F600,
+            // real snowflakes, with and without bit 7 set
+            896_364_389_612_601_344,
+            896_364_389_612_601_472,
+            u64::MAX >> 1,
+        ];
+
+        for id in ids {
+            let emoji = Emoji::from(ReactionType::Custom {
+                animated: false,
+                id: EmojiId(id),
+                name: Some(String::from("emoji")),
+            });
+
+            assert_eq!(emoji, Emoji::Custom(id));
+            round_trip(emoji);
+        }
+    }
+
+    #[test]
+    fn custom_does_not_collide_with_unicode() {
+        // a custom emoji id that is also a valid codepoint
+        let custom = Emoji::Custom(0x1F600);
+
+        assert_ne!(custom.to_text(), unicode("😀").to_text());
+        assert_ne!(custom.to_text(), unicode("128512").to_text());
+    }
+
+    #[test]
+    fn legacy_round_trip() {
+        round_trip(Emoji::Legacy(896_364_389_612_601_472));
+
+        assert_eq!(Emoji::Custom(1).legacy(), Some(Emoji::Legacy(129)));
+
+        // unicode emojis kept their first codepoint
+        assert_eq!(unicode("🇺🇸").legacy(), Some(Emoji::Legacy(0x1F1FA)));
+        assert_eq!(unicode("❤️").legacy(), Some(Emoji::Legacy(0x2764)));
+        assert_eq!(unicode("1️⃣").legacy(), Some(Emoji::Legacy(0x31)));
+        assert_eq!(Emoji::Legacy(0x2764).to_string(), "❤");
+    }
+
+    #[test]
+    fn empty_is_invalid() {
+        assert_eq!(Emoji::from_text(""), None);
+        assert_eq!(Emoji::from_text("custom:"), None);
+        assert_eq!(Emoji::from_text("custom:abc"), None);
+    }
+}
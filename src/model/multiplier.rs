@@ -0,0 +1,178 @@
+//! Experience multipliers.
+
+use super::Error;
+
+use sqlx::{postgres::Postgres, Executor, FromRow};
+
+use twilight_mention::Mention;
+use twilight_model::id::{ChannelId, GuildId, RoleId};
+
+use std::fmt::{self, Display, Formatter};
+
+/// What a [`Multiplier`] applies to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Target {
+    /// Messages sent in a channel.
+    Channel(ChannelId),
+    /// Messages sent in any channel of a category.
+    Category(ChannelId),
+    /// Messages sent by members with a role.
+    Role(RoleId),
+}
+
+impl Target {
+    fn id(&self) -> i64 {
+        match self {
+            Target::Channel(id) | Target::Category(id) => id.0 as i64,
+            Target::Role(id) => id.0 as i64,
+        }
+    }
+
+    fn kind(&self) -> &'static str {
+        match self {
+            Target::Channel(_) => "channel",
+            Target::Category(_) => "category",
+            Target::Role(_) => "role",
+        }
+    }
+}
+
+impl Display for Target {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            Target::Channel(id) => write!(f, "the {} channel", id.mention()),
+            Target::Category(id) => write!(f, "the {} category", id.mention()),
+            Target::Role(id) => write!(f, "the {} role", id.mention()),
+        }
+    }
+}
+
+/// Scales the experience awarded for messages.
+///
+/// A multiplier of 0 means no experience is awarded at all.
+#[derive(Clone, Copy, Debug)]
+pub struct Multiplier {
+    target: Target,
+    multiplier: f64,
+}
+
+impl Multiplier {
+    /// What the multiplier applies to.
+    pub fn target(&self) -> Target {
+        self.target
+    }
+
+    /// How much experience is scaled by.
+    pub fn multiplier(&self) -> f64 {
+        self.multiplier
+    }
+}
+
+/// Figures out how much a message's experience is scaled by.
+///
+/// Every multiplier that applies to the message is multiplied together.
+pub fn multiplier(
+    multipliers: &[Multiplier],
+    channel_id: ChannelId,
+    category_id: Option<ChannelId>,
+    roles: &[RoleId],
+) -> f64 {
+    multipliers
+        .iter()
+        .filter(|m| match m.target {
+            Target::Channel(id) => id == channel_id,
+            Target::Category(id) => Some(id) == category_id,
+            Target::Role(id) => roles.contains(&id),
+        })
+        .map(|m| m.multiplier)
+        .product()
+}
+
+#[derive(FromRow)]
+struct Row {
+    target_id: i64,
+    kind: String,
+    multiplier: f64,
+}
+
+impl From<Row> for Multiplier {
+    fn from(row: Row) -> Multiplier {
+        let target = match row.kind.as_str() {
+            "category" => Target::Category(ChannelId(row.target_id as u64)),
+            "role" => Target::Role(RoleId(row.target_id as u64)),
+            _ => Target::Channel(ChannelId(row.target_id as u64)),
+        };
+
+        Multiplier {
+            target,
+            multiplier: row.multiplier,
+        }
+    }
+}
+
+/// The multipliers of a certain guild.
+pub struct Guild(i64);
+
+impl Guild {
+    /// Create a new `Guild` reference.
+    ///
+    /// This does nothing until operations are made to it.
+    pub fn new(id: GuildId) -> Guild {
+        Guild(id.0 as i64)
+    }
+
+    /// Gets every multiplier of the guild.
+    pub async fn multipliers<'a, E>(&self, ex: E) -> Result<Vec<Multiplier>, Error>
+    where
+        E: Executor<'a, Database = Postgres>,
+    {
+        sqlx::query_as::<_, Row>(
+            r#"
+            SELECT target_id, kind, multiplier FROM xp_multipliers
+            WHERE guild_id = $1
+            ORDER BY kind, target_id
+            "#,
+        )
+        .bind(self.0)
+        .fetch_all(ex)
+        .await
+        .map(|rows| rows.into_iter().map(Multiplier::from).collect())
+    }
+
+    /// Sets the multiplier of a channel, category or role.
+    pub async fn set<'a, E>(&self, ex: E, target: Target, multiplier: f64) -> Result<(), Error>
+    where
+        E: Executor<'a, Database = Postgres>,
+    {
+        sqlx::query(
+            r#"
+            INSERT INTO xp_multipliers (guild_id, target_id, kind, multiplier)
+            VALUES ($1, $2, $3, $4)
+            ON CONFLICT (guild_id, target_id) DO UPDATE
+            SET kind = $3, multiplier = $4
+            "#,
+        )
+        .bind(self.0)
+        .bind(target.id())
+        .bind(target.kind())
+        .bind(multiplier)
+        .execute(ex)
+        .await
+        .map(|_| ())
+    }
+
+    /// Removes the multiplier of a channel, category or role.
+    ///
+    /// Returns `false` if it didn't have one.
+    pub async fn remove<'a, E>(&self, ex: E, target: Target) -> Result<bool, Error>
+    where
+        E: Executor<'a, Database = Postgres>,
+    {
+        sqlx::query("DELETE FROM xp_multipliers WHERE guild_id = $1 AND target_id = $2")
+            .bind(self.0)
+            .bind(target.id())
+            .execute(ex)
+            .await
+            .map(|res| res.rows_affected() > 0)
+    }
+}
@@ -0,0 +1,128 @@
+//! Who may use admin commands in a guild.
+
+use super::Error;
+
+use sqlx::{postgres::Postgres, Executor, FromRow};
+
+use twilight_mention::Mention;
+use twilight_model::application::command::permissions::{
+    CommandPermissions, CommandPermissionsType,
+};
+use twilight_model::id::{GuildId, RoleId, UserId};
+
+use std::fmt::{self, Display, Formatter};
+
+/// A user or role that may use admin commands.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Owner {
+    User(UserId),
+    Role(RoleId),
+}
+
+impl Owner {
+    /// The command permission that grants the owner access to a command.
+    pub fn permission(&self) -> CommandPermissions {
+        let id = match *self {
+            Owner::User(id) => CommandPermissionsType::User(id),
+            Owner::Role(id) => CommandPermissionsType::Role(id),
+        };
+
+        CommandPermissions {
+            id,
+            permission: true,
+        }
+    }
+
+    fn id(&self) -> i64 {
+        match self {
+            Owner::User(id) => id.0 as i64,
+            Owner::Role(id) => id.0 as i64,
+        }
+    }
+}
+
+impl Display for Owner {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            Owner::User(id) => Display::fmt(&id.mention(), f),
+            Owner::Role(id) => Display::fmt(&id.mention(), f),
+        }
+    }
+}
+
+#[derive(FromRow)]
+struct Row {
+    id: i64,
+    is_role: bool,
+}
+
+impl From<Row> for Owner {
+    fn from(row: Row) -> Owner {
+        if row.is_role {
+            Owner::Role(RoleId(row.id as u64))
+        } else {
+            Owner::User(UserId(row.id as u64))
+        }
+    }
+}
+
+/// The owners configured for a certain guild.
+pub struct Guild(i64);
+
+impl Guild {
+    /// Create a new `Guild` reference.
+    ///
+    /// This does nothing until operations are made to it.
+    pub fn new(id: GuildId) -> Guild {
+        Guild(id.0 as i64)
+    }
+
+    /// Gets every owner configured for the guild.
+    pub async fn owners<'a, E>(&self, ex: E) -> Result<Vec<Owner>, Error>
+    where
+        E: Executor<'a, Database = Postgres>,
+    {
+        sqlx::query_as::<_, Row>("SELECT id, is_role FROM command_owners WHERE guild_id = $1")
+            .bind(self.0)
+            .fetch_all(ex)
+            .await
+            .map(|rows| rows.into_iter().map(Owner::from).collect())
+    }
+
+    /// Adds an owner to the guild.
+    ///
+    /// Returns `false` if they were already an owner.
+    pub async fn add<'a, E>(&self, ex: E, owner: Owner) -> Result<bool, Error>
+    where
+        E: Executor<'a, Database = Postgres>,
+    {
+        sqlx::query(
+            r#"
+            INSERT INTO command_owners (guild_id, id, is_role)
+            VALUES ($1, $2, $3)
+            ON CONFLICT DO NOTHING
+            "#,
+        )
+        .bind(self.0)
+        .bind(owner.id())
+        .bind(matches!(owner, Owner::Role(_)))
+        .execute(ex)
+        .await
+        .map(|res| res.rows_affected() > 0)
+    }
+
+    /// Removes an owner from the guild.
+    ///
+    /// Returns `false` if they weren't an owner.
+    pub async fn remove<'a, E>(&self, ex: E, owner: Owner) -> Result<bool, Error>
+    where
+        E: Executor<'a, Database = Postgres>,
+    {
+        sqlx::query("DELETE FROM command_owners WHERE guild_id = $1 AND id = $2")
+            .bind(self.0)
+            .bind(owner.id())
+            .execute(ex)
+            .await
+            .map(|res| res.rows_affected() > 0)
+    }
+}
@@ -0,0 +1,157 @@
+//! Models pertaining to level roles.
+
+use super::super::Error;
+
+use sqlx::{postgres::Postgres, Executor, FromRow};
+
+use twilight_model::id::{GuildId, RoleId};
+
+/// A role given to users that reach a level.
+#[derive(Debug, FromRow)]
+pub struct LevelRole {
+    level: i32,
+    role_id: i64,
+}
+
+impl LevelRole {
+    /// The level needed to get the role.
+    pub fn level(&self) -> i32 {
+        self.level
+    }
+
+    /// The role given.
+    pub fn role_id(&self) -> RoleId {
+        RoleId(self.role_id as u64)
+    }
+}
+
+/// What happens to lower level roles when a user gets a new one.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Mode {
+    /// Users keep every role they've earned.
+    #[default]
+    Stack,
+    /// Users only keep the role of the highest level they've reached.
+    Replace,
+}
+
+/// Figures out which level roles a user at `level` should have.
+///
+/// `roles` must be sorted by level, like [`Guild::roles`] returns them.
+pub fn wanted(roles: &[LevelRole], mode: Mode, level: i32) -> Vec<RoleId> {
+    let earned = roles.iter().filter(|role| role.level <= level);
+
+    match mode {
+        Mode::Stack => earned.map(LevelRole::role_id).collect(),
+        Mode::Replace => {
+            // there may be multiple roles for the same level
+            let highest = earned.clone().map(LevelRole::level).max();
+
+            earned
+                .filter(|role| Some(role.level) == highest)
+                .map(LevelRole::role_id)
+                .collect()
+        }
+    }
+}
+
+/// The level roles of a certain guild.
+pub struct Guild(i64);
+
+impl Guild {
+    /// Create a new `Guild` reference.
+    ///
+    /// This does nothing until operations are made to it.
+    pub fn new(id: GuildId) -> Guild {
+        Guild(id.0 as i64)
+    }
+
+    /// Gets every level role of the guild, sorted by level.
+    pub async fn roles<'a, E>(&self, ex: E) -> Result<Vec<LevelRole>, Error>
+    where
+        E: Executor<'a, Database = Postgres>,
+    {
+        sqlx::query_as(
+            r#"
+            SELECT level, role_id FROM level_roles WHERE guild_id = $1
+            ORDER BY level, role_id
+            "#,
+        )
+        .bind(self.0)
+        .fetch_all(ex)
+        .await
+    }
+
+    /// Gives a role to users that reach a level.
+    ///
+    /// If the role was already a level role, its level is changed.
+    pub async fn add<'a, E>(&self, ex: E, level: i32, role_id: RoleId) -> Result<(), Error>
+    where
+        E: Executor<'a, Database = Postgres>,
+    {
+        sqlx::query(
+            r#"
+            INSERT INTO level_roles (guild_id, level, role_id)
+            VALUES ($1, $2, $3)
+            ON CONFLICT (guild_id, role_id) DO UPDATE
+            SET level = $2
+            "#,
+        )
+        .bind(self.0)
+        .bind(level)
+        .bind(role_id.0 as i64)
+        .execute(ex)
+        .await
+        .map(|_| ())
+    }
+
+    /// Removes a level role.
+    ///
+    /// Returns `false` if it wasn't a level role.
+    pub async fn remove<'a, E>(&self, ex: E, role_id: RoleId) -> Result<bool, Error>
+    where
+        E: Executor<'a, Database = Postgres>,
+    {
+        sqlx::query("DELETE FROM level_roles WHERE guild_id = $1 AND role_id = $2")
+            .bind(self.0)
+            .bind(role_id.0 as i64)
+            .execute(ex)
+            .await
+            .map(|res| res.rows_affected() > 0)
+    }
+
+    /// Gets the level role mode of the guild.
+    pub async fn mode<'a, E>(&self, ex: E) -> Result<Mode, Error>
+    where
+        E: Executor<'a, Database = Postgres>,
+    {
+        sqlx::query_scalar("SELECT replace FROM level_role_modes WHERE guild_id = $1")
+            .bind(self.0)
+            .fetch_optional(ex)
+            .await
+            .map(|replace| match replace {
+                Some(true) => Mode::Replace,
+                _ => Mode::Stack,
+            })
+    }
+
+    /// Sets the level role mode of the guild.
+    pub async fn set_mode<'a, E>(&self, ex: E, mode: Mode) -> Result<(), Error>
+    where
+        E: Executor<'a, Database = Postgres>,
+    {
+        sqlx::query(
+            r#"
+            INSERT INTO level_role_modes (guild_id, replace)
+            VALUES ($1, $2)
+            ON CONFLICT (guild_id) DO UPDATE
+            SET replace = $2
+            "#,
+        )
+        .bind(self.0)
+        .bind(mode == Mode::Replace)
+        .execute(ex)
+        .await
+        .map(|_| ())
+    }
+}
@@ -0,0 +1,297 @@
+//! Models pertaining to role menus.
+
+use super::super::{Emoji, Error};
+
+use sqlx::{postgres::Postgres, Executor, FromRow};
+
+use twilight_model::id::{ChannelId, GuildId, MessageId, RoleId};
+
+/// How a role menu lets users pick roles.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Kind {
+    /// A button for every role, which toggles it.
+    Buttons,
+    /// A select menu where users pick every role they want.
+    Select,
+}
+
+impl Kind {
+    fn name(&self) -> &'static str {
+        match self {
+            Kind::Buttons => "buttons",
+            Kind::Select => "select",
+        }
+    }
+
+    fn from_name(name: &str) -> Kind {
+        match name {
+            "select" => Kind::Select,
+            _ => Kind::Buttons,
+        }
+    }
+}
+
+/// A message posted by the bot that lets users pick roles.
+#[derive(Debug, FromRow)]
+pub struct RoleMenu {
+    id: i64,
+    guild_id: i64,
+    channel_id: i64,
+    message_id: i64,
+    kind: String,
+    content: String,
+}
+
+impl RoleMenu {
+    /// The id of the menu, which admins use to edit it.
+    pub fn id(&self) -> i64 {
+        self.id
+    }
+
+    /// The id of the guild the menu is in.
+    pub fn guild_id(&self) -> GuildId {
+        GuildId(self.guild_id as u64)
+    }
+
+    /// The id of the channel the menu was posted in.
+    pub fn channel_id(&self) -> ChannelId {
+        ChannelId(self.channel_id as u64)
+    }
+
+    /// The id of the message the menu was posted as.
+    pub fn message_id(&self) -> MessageId {
+        MessageId(self.message_id as u64)
+    }
+
+    /// How users pick roles.
+    pub fn kind(&self) -> Kind {
+        Kind::from_name(&self.kind)
+    }
+
+    /// The text of the message.
+    pub fn content(&self) -> &str {
+        &self.content
+    }
+}
+
+/// A role that can be picked from a [`RoleMenu`].
+#[derive(Debug, FromRow)]
+pub struct MenuRole {
+    role_id: i64,
+    label: String,
+    emoji: Option<Emoji>,
+}
+
+impl MenuRole {
+    /// The role to give.
+    pub fn role_id(&self) -> RoleId {
+        RoleId(self.role_id as u64)
+    }
+
+    /// The text of the button or option.
+    pub fn label(&self) -> &str {
+        &self.label
+    }
+
+    /// The emoji of the button or option.
+    pub fn emoji(&self) -> Option<&Emoji> {
+        self.emoji.as_ref()
+    }
+}
+
+/// The role menus of a certain guild.
+pub struct Guild(i64);
+
+impl Guild {
+    /// Create a new `Guild` reference.
+    ///
+    /// This does nothing until operations are made to it.
+    pub fn new(id: GuildId) -> Guild {
+        Guild(id.0 as i64)
+    }
+
+    /// Gets every role menu in the guild.
+    pub async fn menus<'a, E>(&self, ex: E) -> Result<Vec<RoleMenu>, Error>
+    where
+        E: Executor<'a, Database = Postgres>,
+    {
+        sqlx::query_as("SELECT * FROM role_menus WHERE guild_id = $1 ORDER BY id")
+            .bind(self.0)
+            .fetch_all(ex)
+            .await
+    }
+
+    /// Gets a role menu by its id.
+    pub async fn menu<'a, E>(&self, ex: E, id: i64) -> Result<Option<RoleMenu>, Error>
+    where
+        E: Executor<'a, Database = Postgres>,
+    {
+        sqlx::query_as("SELECT * FROM role_menus WHERE guild_id = $1 AND id = $2")
+            .bind(self.0)
+            .bind(id)
+            .fetch_optional(ex)
+            .await
+    }
+
+    /// Gets the role menu posted as a message.
+    pub async fn by_message<'a, E>(
+        &self,
+        ex: E,
+        message_id: MessageId,
+    ) -> Result<Option<RoleMenu>, Error>
+    where
+        E: Executor<'a, Database = Postgres>,
+    {
+        sqlx::query_as("SELECT * FROM role_menus WHERE guild_id = $1 AND message_id = $2")
+            .bind(self.0)
+            .bind(message_id.0 as i64)
+            .fetch_optional(ex)
+            .await
+    }
+
+    /// Saves a role menu that was just posted.
+    pub async fn create<'a, E>(
+        &self,
+        ex: E,
+        channel_id: ChannelId,
+        message_id: MessageId,
+        kind: Kind,
+        content: &str,
+    ) -> Result<RoleMenu, Error>
+    where
+        E: Executor<'a, Database = Postgres>,
+    {
+        sqlx::query_as(
+            r#"
+            INSERT INTO role_menus (guild_id, channel_id, message_id, kind, content)
+            VALUES ($1, $2, $3, $4, $5)
+            RETURNING *
+            "#,
+        )
+        .bind(self.0)
+        .bind(channel_id.0 as i64)
+        .bind(message_id.0 as i64)
+        .bind(kind.name())
+        .bind(content)
+        .fetch_one(ex)
+        .await
+    }
+
+    /// Changes how a role menu looks.
+    ///
+    /// `None` leaves that part of the menu unchanged. Returns the updated
+    /// menu, or `None` if it doesn't exist.
+    pub async fn edit<'a, E>(
+        &self,
+        ex: E,
+        id: i64,
+        kind: Option<Kind>,
+        content: Option<&str>,
+    ) -> Result<Option<RoleMenu>, Error>
+    where
+        E: Executor<'a, Database = Postgres>,
+    {
+        sqlx::query_as(
+            r#"
+            UPDATE role_menus
+            SET kind = COALESCE($3, kind), content = COALESCE($4, content)
+            WHERE guild_id = $1 AND id = $2
+            RETURNING *
+            "#,
+        )
+        .bind(self.0)
+        .bind(id)
+        .bind(kind.map(|kind| kind.name()))
+        .bind(content)
+        .fetch_optional(ex)
+        .await
+    }
+
+    /// Deletes a role menu and its roles.
+    ///
+    /// Returns `false` if it didn't exist.
+    pub async fn delete<'a, E>(&self, ex: E, id: i64) -> Result<bool, Error>
+    where
+        E: Executor<'a, Database = Postgres>,
+    {
+        sqlx::query("DELETE FROM role_menus WHERE guild_id = $1 AND id = $2")
+            .bind(self.0)
+            .bind(id)
+            .execute(ex)
+            .await
+            .map(|res| res.rows_affected() > 0)
+    }
+
+    /// Gets the roles of a role menu, in the order they were added.
+    pub async fn roles<'a, E>(&self, ex: E, id: i64) -> Result<Vec<MenuRole>, Error>
+    where
+        E: Executor<'a, Database = Postgres>,
+    {
+        sqlx::query_as(
+            r#"
+            SELECT r.role_id, r.label, r.emoji FROM role_menu_roles r
+            JOIN role_menus m ON m.id = r.menu_id
+            WHERE m.guild_id = $1 AND r.menu_id = $2
+            ORDER BY r.added_at, r.role_id
+            "#,
+        )
+        .bind(self.0)
+        .bind(id)
+        .fetch_all(ex)
+        .await
+    }
+
+    /// Adds a role to a role menu, or changes how it looks if it is already
+    /// in the menu.
+    ///
+    /// Returns `false` if the menu doesn't exist.
+    pub async fn add_role<'a, E>(
+        &self,
+        ex: E,
+        id: i64,
+        role_id: RoleId,
+        label: &str,
+        emoji: Option<&Emoji>,
+    ) -> Result<bool, Error>
+    where
+        E: Executor<'a, Database = Postgres>,
+    {
+        sqlx::query(
+            r#"
+            INSERT INTO role_menu_roles (menu_id, role_id, label, emoji)
+            SELECT id, $3, $4, $5 FROM role_menus WHERE guild_id = $1 AND id = $2
+            ON CONFLICT (menu_id, role_id) DO UPDATE
+            SET label = $4, emoji = $5
+            "#,
+        )
+        .bind(self.0)
+        .bind(id)
+        .bind(role_id.0 as i64)
+        .bind(label)
+        .bind(emoji)
+        .execute(ex)
+        .await
+        .map(|res| res.rows_affected() > 0)
+    }
+
+    /// Removes a role from a role menu.
+    ///
+    /// Returns `false` if it wasn't in the menu.
+    pub async fn remove_role<'a, E>(&self, ex: E, id: i64, role_id: RoleId) -> Result<bool, Error>
+    where
+        E: Executor<'a, Database = Postgres>,
+    {
+        sqlx::query(
+            r#"
+            DELETE FROM role_menu_roles r USING role_menus m
+            WHERE m.id = r.menu_id AND m.guild_id = $1 AND r.menu_id = $2 AND r.role_id = $3
+            "#,
+        )
+        .bind(self.0)
+        .bind(id)
+        .bind(role_id.0 as i64)
+        .execute(ex)
+        .await
+        .map(|res| res.rows_affected() > 0)
+    }
+}
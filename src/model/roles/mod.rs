@@ -1 +1,3 @@
+pub mod level;
+pub mod menu;
 pub mod reaction;
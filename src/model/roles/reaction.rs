@@ -6,10 +6,49 @@ use sqlx::{postgres::Postgres, Executor, FromRow};
 
 use std::fmt::{self, Display, Formatter};
 
-use twilight_model::id::{ChannelId, GuildId, MessageId, RoleId};
+use twilight_model::id::{ChannelId, EmojiId, GuildId, MessageId, RoleId};
+
+/// How a reaction role behaves when reacted to.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Mode {
+    /// Reacting gives the role, and unreacting takes it away.
+    #[default]
+    Normal,
+    /// Like [`Mode::Normal`], but reacting also takes away the other unique
+    /// roles on the message, along with their reactions.
+    Unique,
+    /// Reacting gives the role, and it is never taken away.
+    Verify,
+    /// Reacting takes the role away, and it is never given.
+    Drop,
+    /// Reacting gives the role if the user doesn't have it, or takes it away
+    /// if they do. The reaction is removed so it can be used again.
+    Toggle,
+}
+
+impl Mode {
+    fn name(&self) -> &'static str {
+        match self {
+            Mode::Normal => "normal",
+            Mode::Unique => "unique",
+            Mode::Verify => "verify",
+            Mode::Drop => "drop",
+            Mode::Toggle => "toggle",
+        }
+    }
+
+    fn from_name(name: &str) -> Mode {
+        match name {
+            "unique" => Mode::Unique,
+            "verify" => Mode::Verify,
+            "drop" => Mode::Drop,
+            "toggle" => Mode::Toggle,
+            _ => Mode::Normal,
+        }
+    }
+}
 
 #[derive(FromRow)]
-#[allow(dead_code)]
 pub struct ReactionRole {
     guild_id: i64,
     message_id: i64,
@@ -18,15 +57,45 @@ pub struct ReactionRole {
     role_id: i64,
 
     emoji: Emoji,
+
+    mode: String,
 }
 
 impl ReactionRole {
+    /// The id of the guild the reaction role is in.
+    pub fn guild_id(&self) -> GuildId {
+        GuildId(self.guild_id as u64)
+    }
+
+    /// The id of the message to react to.
+    pub fn message_id(&self) -> MessageId {
+        MessageId(self.message_id as u64)
+    }
+
+    /// The id of the channel the message is in.
+    pub fn channel_id(&self) -> ChannelId {
+        ChannelId(self.channel_id as u64)
+    }
+
     /// The role id the reaction role pertains to.
     pub fn role_id(&self) -> RoleId {
         RoleId(self.role_id as u64)
     }
 
+    /// The emoji to react with.
+    pub fn emoji(&self) -> &Emoji {
+        &self.emoji
+    }
+
+    /// How the reaction role behaves.
+    pub fn mode(&self) -> Mode {
+        Mode::from_name(&self.mode)
+    }
+
     /// Gets a `ReactionRole` by a message and the emoji.
+    ///
+    /// If the reaction role was stored as an [`Emoji::Legacy`], it is
+    /// replaced with `emoji`.
     pub async fn get<'a, E>(
         ex: E,
         message_id: MessageId,
@@ -35,11 +104,222 @@ impl ReactionRole {
     where
         E: Executor<'a, Database = Postgres>,
     {
-        sqlx::query_as("SELECT * FROM reaction_roles WHERE message_id = $1 AND emoji = $2")
+        let legacy = emoji.legacy();
+
+        sqlx::query_as(
+            r#"
+            WITH found AS (
+                SELECT * FROM reaction_roles
+                WHERE message_id = $1 AND emoji IN ($2, $3)
+                ORDER BY emoji = $2 DESC
+                LIMIT 1
+            ),
+            replaced AS (
+                UPDATE reaction_roles SET emoji = $2
+                WHERE message_id = $1 AND emoji = $3
+                    AND EXISTS (SELECT 1 FROM found WHERE found.emoji = $3)
+            )
+            SELECT * FROM found
+            "#,
+        )
+        .bind(message_id.0 as i64)
+        .bind(emoji)
+        .bind(legacy)
+        .fetch_optional(ex)
+        .await
+    }
+}
+
+/// The reaction roles of a certain guild.
+pub struct Guild(i64);
+
+impl Guild {
+    /// Create a new `Guild` reference.
+    ///
+    /// This does nothing until operations are made to it.
+    pub fn new(id: GuildId) -> Guild {
+        Guild(id.0 as i64)
+    }
+
+    /// Gets every reaction role in the guild, grouped by message.
+    pub async fn reaction_roles<'a, E>(&self, ex: E) -> Result<Vec<ReactionRole>, Error>
+    where
+        E: Executor<'a, Database = Postgres>,
+    {
+        sqlx::query_as(
+            r#"
+            SELECT * FROM reaction_roles WHERE guild_id = $1
+            ORDER BY channel_id, message_id, role_id
+            "#,
+        )
+        .bind(self.0)
+        .fetch_all(ex)
+        .await
+    }
+
+    /// Gets the reaction roles on a message.
+    pub async fn by_message<'a, E>(
+        &self,
+        ex: E,
+        message_id: MessageId,
+    ) -> Result<Vec<ReactionRole>, Error>
+    where
+        E: Executor<'a, Database = Postgres>,
+    {
+        sqlx::query_as(
+            r#"
+            SELECT * FROM reaction_roles WHERE guild_id = $1 AND message_id = $2
+            ORDER BY role_id
+            "#,
+        )
+        .bind(self.0)
+        .bind(message_id.0 as i64)
+        .fetch_all(ex)
+        .await
+    }
+
+    /// Removes the reaction role for an emoji on a message.
+    ///
+    /// Returns `false` if there wasn't one.
+    pub async fn remove<'a, E>(
+        &self,
+        ex: E,
+        message_id: MessageId,
+        emoji: &Emoji,
+    ) -> Result<bool, Error>
+    where
+        E: Executor<'a, Database = Postgres>,
+    {
+        sqlx::query(
+            r#"
+            DELETE FROM reaction_roles
+            WHERE guild_id = $1 AND message_id = $2 AND emoji IN ($3, $4)
+            "#,
+        )
+        .bind(self.0)
+        .bind(message_id.0 as i64)
+        .bind(emoji)
+        .bind(emoji.legacy())
+        .execute(ex)
+        .await
+        .map(|res| res.rows_affected() > 0)
+    }
+
+    /// Sets the mode of the reaction role for an emoji on a message, or of
+    /// every reaction role on the message if `emoji` is `None`.
+    ///
+    /// Returns how many reaction roles were changed.
+    pub async fn set_mode<'a, E>(
+        &self,
+        ex: E,
+        message_id: MessageId,
+        emoji: Option<&Emoji>,
+        mode: Mode,
+    ) -> Result<u64, Error>
+    where
+        E: Executor<'a, Database = Postgres>,
+    {
+        sqlx::query(
+            r#"
+            UPDATE reaction_roles SET mode = $3
+            WHERE guild_id = $1 AND message_id = $2
+                AND ($4::TEXT IS NULL OR emoji IN ($4, $5))
+            "#,
+        )
+        .bind(self.0)
+        .bind(message_id.0 as i64)
+        .bind(mode.name())
+        .bind(emoji)
+        .bind(emoji.and_then(Emoji::legacy))
+        .execute(ex)
+        .await
+        .map(|res| res.rows_affected())
+    }
+
+    /// Removes every reaction role on a message.
+    ///
+    /// Returns how many were removed.
+    pub async fn clear<'a, E>(&self, ex: E, message_id: MessageId) -> Result<u64, Error>
+    where
+        E: Executor<'a, Database = Postgres>,
+    {
+        sqlx::query("DELETE FROM reaction_roles WHERE guild_id = $1 AND message_id = $2")
+            .bind(self.0)
             .bind(message_id.0 as i64)
-            .bind(emoji)
-            .fetch_optional(ex)
+            .execute(ex)
+            .await
+            .map(|res| res.rows_affected())
+    }
+
+    /// Removes every reaction role on deleted messages.
+    ///
+    /// Returns how many were removed.
+    pub async fn remove_messages<'a, E>(
+        &self,
+        ex: E,
+        message_ids: &[MessageId],
+    ) -> Result<u64, Error>
+    where
+        E: Executor<'a, Database = Postgres>,
+    {
+        let message_ids = message_ids.iter().map(|id| id.0 as i64).collect::<Vec<_>>();
+
+        sqlx::query("DELETE FROM reaction_roles WHERE guild_id = $1 AND message_id = ANY($2)")
+            .bind(self.0)
+            .bind(message_ids)
+            .execute(ex)
+            .await
+            .map(|res| res.rows_affected())
+    }
+
+    /// Removes every reaction role in a deleted channel.
+    ///
+    /// Returns how many were removed.
+    pub async fn remove_channel<'a, E>(&self, ex: E, channel_id: ChannelId) -> Result<u64, Error>
+    where
+        E: Executor<'a, Database = Postgres>,
+    {
+        sqlx::query("DELETE FROM reaction_roles WHERE guild_id = $1 AND channel_id = $2")
+            .bind(self.0)
+            .bind(channel_id.0 as i64)
+            .execute(ex)
+            .await
+            .map(|res| res.rows_affected())
+    }
+
+    /// Removes every reaction role that gives a deleted role.
+    ///
+    /// Returns how many were removed.
+    pub async fn remove_role<'a, E>(&self, ex: E, role_id: RoleId) -> Result<u64, Error>
+    where
+        E: Executor<'a, Database = Postgres>,
+    {
+        sqlx::query("DELETE FROM reaction_roles WHERE guild_id = $1 AND role_id = $2")
+            .bind(self.0)
+            .bind(role_id.0 as i64)
+            .execute(ex)
+            .await
+            .map(|res| res.rows_affected())
+    }
+
+    /// Removes every reaction role for deleted custom emojis.
+    ///
+    /// Returns how many were removed.
+    pub async fn remove_emojis<'a, E>(&self, ex: E, emoji_ids: &[EmojiId]) -> Result<u64, Error>
+    where
+        E: Executor<'a, Database = Postgres>,
+    {
+        let emojis = emoji_ids
+            .iter()
+            .map(|id| Emoji::Custom(id.0).to_text())
+            .collect::<Vec<_>>();
+
+        sqlx::query("DELETE FROM reaction_roles WHERE guild_id = $1 AND emoji = ANY($2)")
+            .bind(self.0)
+            .bind(emojis)
+            .execute(ex)
             .await
+            .map(|res| res.rows_affected())
     }
 }
 
@@ -69,14 +349,15 @@ impl Message {
         ex: E,
         role_id: RoleId,
         emoji: Emoji,
+        mode: Mode,
     ) -> Result<(), CreateError>
     where
         E: Executor<'a, Database = Postgres>,
     {
         sqlx::query(
             r#"
-            INSERT INTO reaction_roles (guild_id, message_id, channel_id, role_id, emoji)
-            VALUES ($1, $2, $3, $4, $5)
+            INSERT INTO reaction_roles (guild_id, message_id, channel_id, role_id, emoji, mode)
+            VALUES ($1, $2, $3, $4, $5, $6)
             "#,
         )
         .bind(self.guild_id.0 as i64)
@@ -84,6 +365,7 @@ impl Message {
         .bind(self.channel_id.0 as i64)
         .bind(role_id.0 as i64)
         .bind(emoji)
+        .bind(mode.name())
         .execute(ex)
         .await
         .map(|_| ())
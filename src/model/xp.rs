@@ -1,11 +1,14 @@
 //! User experience.
 
+use super::level::Curve;
 use super::Error;
 
 use sqlx::{postgres::Postgres, Executor, FromRow};
 
 use twilight_model::id::{GuildId, UserId};
 
+use std::time::{SystemTime, UNIX_EPOCH};
+
 /// A user's experience.
 #[derive(Debug, FromRow)]
 pub struct Record {
@@ -30,9 +33,126 @@ impl Record {
         self.score
     }
 
-    /// What level the user is at.
-    pub fn level(&self) -> i32 {
-        level(self.score)
+    /// What level the user is at in a level curve.
+    pub fn level(&self, curve: &Curve) -> i32 {
+        curve.level(self.score)
+    }
+}
+
+/// The experience a user gained from messages in a period of time.
+#[derive(Debug, FromRow)]
+pub struct Gained {
+    guild_id: i64,
+    user_id: i64,
+    amount: i64,
+}
+
+impl Gained {
+    /// The id of the guild the experience was gained in.
+    pub fn guild_id(&self) -> GuildId {
+        GuildId(self.guild_id as u64)
+    }
+
+    /// The id of the user.
+    pub fn user_id(&self) -> UserId {
+        UserId(self.user_id as u64)
+    }
+
+    /// How much experience the user gained.
+    pub fn amount(&self) -> i64 {
+        self.amount
+    }
+}
+
+/// A user's position in a guild's leaderboard.
+#[derive(Debug, FromRow)]
+pub struct Rank {
+    position: i64,
+    total: i64,
+}
+
+impl Rank {
+    /// The position of the user, starting at 1.
+    pub fn position(&self) -> i64 {
+        self.position
+    }
+
+    /// How many users are in the leaderboard.
+    pub fn total(&self) -> i64 {
+        self.total
+    }
+}
+
+/// Where a change in experience came from.
+///
+/// Every change is kept in a ledger, which is used for leaderboards over a
+/// window of time and for auditing.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Source {
+    /// Sending messages.
+    Message,
+    /// An admin changing it with `/xp`.
+    Admin,
+    /// Being inactive; see [`decay`](super::decay::decay).
+    Decay,
+}
+
+impl Source {
+    fn name(&self) -> &'static str {
+        match self {
+            Source::Message => "message",
+            Source::Admin => "admin",
+            Source::Decay => "decay",
+        }
+    }
+}
+
+/// Experience earned by a user that hasn't been saved yet.
+///
+/// `score` is the experience earned for every message but the first. The
+/// first message is awarded by [`award`], based on when the user was last
+/// awarded, and scaled by `multiplier`.
+#[derive(Clone, Debug)]
+pub struct Award {
+    pub guild_id: GuildId,
+    pub user_id: UserId,
+    /// When the first message was sent.
+    pub first_at: SystemTime,
+    /// When the last message was sent.
+    pub last_at: SystemTime,
+    pub score: i32,
+    /// How much the first message's experience is scaled by.
+    pub multiplier: f64,
+}
+
+/// A user's experience after an [`Award`] was saved.
+#[derive(Debug, FromRow)]
+pub struct Awarded {
+    guild_id: i64,
+    user_id: i64,
+    score: i32,
+    awarded: i32,
+}
+
+impl Awarded {
+    /// The id of the guild.
+    pub fn guild_id(&self) -> GuildId {
+        GuildId(self.guild_id as u64)
+    }
+
+    /// The id of the user.
+    pub fn user_id(&self) -> UserId {
+        UserId(self.user_id as u64)
+    }
+
+    /// The user's new score.
+    pub fn score(&self) -> i32 {
+        self.score
+    }
+
+    /// How much experience the user was awarded.
+    pub fn awarded(&self) -> i32 {
+        self.awarded
     }
 }
 
@@ -58,7 +178,7 @@ impl Guild {
         sqlx::query_as(
             r#"
             SELECT * FROM xp WHERE guild_id = $1
-            ORDER BY score DESC
+            ORDER BY score DESC, user_id
             LIMIT $2 OFFSET $3
             "#,
         )
@@ -69,6 +189,148 @@ impl Guild {
         .await
     }
 
+    /// Counts the users with a record in the guild.
+    pub async fn count<'a, E>(&self, ex: E) -> Result<i64, Error>
+    where
+        E: Executor<'a, Database = Postgres>,
+    {
+        sqlx::query_scalar("SELECT COUNT(*) FROM xp WHERE guild_id = $1")
+            .bind(self.0)
+            .fetch_one(ex)
+            .await
+    }
+
+    /// Gets the position of a user in the guild's leaderboard.
+    ///
+    /// This uses the same ordering as [`Guild::top`]. Returns `None` if the
+    /// user doesn't have a record.
+    pub async fn rank_of<'a, E>(&self, ex: E, user_id: UserId) -> Result<Option<Rank>, Error>
+    where
+        E: Executor<'a, Database = Postgres>,
+    {
+        sqlx::query_as(
+            r#"
+            SELECT position, total FROM (
+                SELECT
+                    user_id,
+                    ROW_NUMBER() OVER (ORDER BY score DESC, user_id) AS position,
+                    COUNT(*) OVER () AS total
+                FROM xp WHERE guild_id = $1
+            ) ranked
+            WHERE user_id = $2
+            "#,
+        )
+        .bind(self.0)
+        .bind(user_id.0 as i64)
+        .fetch_optional(ex)
+        .await
+    }
+
+    /// Gets the top `count` users by experience gained since `since`.
+    ///
+    /// Only experience from messages counts, so admin changes and decay don't
+    /// move users around the leaderboard. Users that didn't gain any
+    /// experience in that time are left out.
+    pub async fn top_since<'a, E>(
+        &self,
+        ex: E,
+        since: SystemTime,
+        count: u64,
+        page: u64,
+    ) -> Result<Vec<Gained>, Error>
+    where
+        E: Executor<'a, Database = Postgres>,
+    {
+        let count = count as i64;
+        let offset = count * (page as i64);
+
+        // the sum of INTEGERs is a BIGINT
+        sqlx::query_as(
+            r#"
+            SELECT guild_id, user_id, SUM(amount) AS amount FROM xp_ledger
+            WHERE guild_id = $1 AND created_at >= to_timestamp($2) AND source = 'message'
+            GROUP BY guild_id, user_id
+            HAVING SUM(amount) > 0
+            ORDER BY amount DESC, user_id
+            LIMIT $3 OFFSET $4
+            "#,
+        )
+        .bind(self.0)
+        .bind(epoch(since))
+        .bind(count)
+        .bind(offset)
+        .fetch_all(ex)
+        .await
+    }
+
+    /// Counts the users that gained experience from messages since `since`.
+    pub async fn count_since<'a, E>(&self, ex: E, since: SystemTime) -> Result<i64, Error>
+    where
+        E: Executor<'a, Database = Postgres>,
+    {
+        sqlx::query_scalar(
+            r#"
+            SELECT COUNT(*) FROM (
+                SELECT user_id FROM xp_ledger
+                WHERE guild_id = $1 AND created_at >= to_timestamp($2) AND source = 'message'
+                GROUP BY user_id
+                HAVING SUM(amount) > 0
+            ) gained
+            "#,
+        )
+        .bind(self.0)
+        .bind(epoch(since))
+        .fetch_one(ex)
+        .await
+    }
+
+    /// Gets the position of a user in the guild's leaderboard since `since`.
+    ///
+    /// This uses the same ordering as [`Guild::top_since`]. Returns `None` if
+    /// the user didn't gain any experience in that time.
+    pub async fn rank_of_since<'a, E>(
+        &self,
+        ex: E,
+        user_id: UserId,
+        since: SystemTime,
+    ) -> Result<Option<Rank>, Error>
+    where
+        E: Executor<'a, Database = Postgres>,
+    {
+        sqlx::query_as(
+            r#"
+            SELECT position, total FROM (
+                SELECT
+                    user_id,
+                    ROW_NUMBER() OVER (ORDER BY SUM(amount) DESC, user_id) AS position,
+                    COUNT(*) OVER () AS total
+                FROM xp_ledger
+                WHERE guild_id = $1 AND created_at >= to_timestamp($3) AND source = 'message'
+                GROUP BY user_id
+                HAVING SUM(amount) > 0
+            ) ranked
+            WHERE user_id = $2
+            "#,
+        )
+        .bind(self.0)
+        .bind(user_id.0 as i64)
+        .bind(epoch(since))
+        .fetch_optional(ex)
+        .await
+    }
+
+    /// Gets every user in the guild with at least `score` experience.
+    pub async fn at_least<'a, E>(&self, ex: E, score: i32) -> Result<Vec<Record>, Error>
+    where
+        E: Executor<'a, Database = Postgres>,
+    {
+        sqlx::query_as("SELECT * FROM xp WHERE guild_id = $1 AND score >= $2")
+            .bind(self.0)
+            .bind(score)
+            .fetch_all(ex)
+            .await
+    }
+
     /// Gets a user's experience level.
     ///
     /// If a row doesn't exist, it will return a `User` with zero xp.
@@ -93,46 +355,245 @@ impl Guild {
     }
 
     /// Gives (or takes away) some experience to a user.
-    pub async fn add<'a, E>(&self, ex: E, user_id: UserId, score: i32) -> Result<(), Error>
+    ///
+    /// Returns the user's new score.
+    pub async fn add<'a, E>(
+        &self,
+        ex: E,
+        user_id: UserId,
+        score: i32,
+        source: Source,
+    ) -> Result<i32, Error>
     where
-        E: Executor<'a, Database = Postgres> + Clone,
+        E: Executor<'a, Database = Postgres>,
     {
-        let user_id = user_id.0 as i64;
-
-        let res = sqlx::query(
+        // create the record if it doesn't exist yet
+        sqlx::query_scalar(
             r#"
-            UPDATE xp 
-            SET score = score + $3 
-            WHERE guild_id = $1 AND user_id = $2
+            WITH upserted AS (
+                INSERT INTO xp (guild_id, user_id, score)
+                VALUES ($1, $2, $3)
+                ON CONFLICT (user_id, guild_id) DO UPDATE
+                SET score = xp.score + $3
+                RETURNING score
+            ),
+            ledger AS (
+                INSERT INTO xp_ledger (guild_id, user_id, amount, source)
+                SELECT $1, $2, $3, $4 WHERE $3 <> 0
+            )
+            SELECT score FROM upserted
             "#,
         )
         .bind(self.0)
-        .bind(user_id)
+        .bind(user_id.0 as i64)
         .bind(score)
-        .execute(ex.clone())
-        .await?;
-
-        // if no records were updated, the user record doesn't exist!
-        if res.rows_affected() == 0 {
-            // insert it instead
-            sqlx::query(
-                r#"
+        .bind(source.name())
+        .fetch_one(ex)
+        .await
+    }
+
+    /// Sets the experience of a user.
+    ///
+    /// The difference from their old score is recorded in the ledger.
+    pub async fn set<'a, E>(
+        &self,
+        ex: E,
+        user_id: UserId,
+        score: i32,
+        source: Source,
+    ) -> Result<(), Error>
+    where
+        E: Executor<'a, Database = Postgres>,
+    {
+        sqlx::query(
+            r#"
+            WITH previous AS (
+                SELECT COALESCE(
+                    (SELECT score FROM xp WHERE guild_id = $1 AND user_id = $2),
+                    0
+                ) AS score
+            ),
+            upserted AS (
                 INSERT INTO xp (guild_id, user_id, score)
                 VALUES ($1, $2, $3)
-                "#,
+                ON CONFLICT (user_id, guild_id) DO UPDATE
+                SET score = $3
             )
-            .bind(self.0)
-            .bind(user_id)
-            .bind(score)
-            .execute(ex)
-            .await?;
-        }
+            INSERT INTO xp_ledger (guild_id, user_id, amount, source)
+            SELECT $1, $2, $3 - previous.score, $4 FROM previous
+            WHERE previous.score <> $3
+            "#,
+        )
+        .bind(self.0)
+        .bind(user_id.0 as i64)
+        .bind(score)
+        .bind(source.name())
+        .execute(ex)
+        .await
+        .map(|_| ())
+    }
+
+    /// Removes a user's record.
+    ///
+    /// The ledger is kept, and the score they had is taken back in it.
+    /// Returns `false` if they didn't have one.
+    pub async fn remove<'a, E>(&self, ex: E, user_id: UserId, source: Source) -> Result<bool, Error>
+    where
+        E: Executor<'a, Database = Postgres>,
+    {
+        sqlx::query_scalar::<_, i64>(
+            r#"
+            WITH removed AS (
+                DELETE FROM xp WHERE guild_id = $1 AND user_id = $2
+                RETURNING guild_id, user_id, score
+            ),
+            ledger AS (
+                INSERT INTO xp_ledger (guild_id, user_id, amount, source)
+                SELECT guild_id, user_id, -score, $3 FROM removed WHERE score <> 0
+            )
+            SELECT COUNT(*) FROM removed
+            "#,
+        )
+        .bind(self.0)
+        .bind(user_id.0 as i64)
+        .bind(source.name())
+        .fetch_one(ex)
+        .await
+        .map(|removed| removed > 0)
+    }
+
+    /// Removes every record in the guild.
+    ///
+    /// The ledger is kept, and every score is taken back in it. Returns how
+    /// many records were removed.
+    pub async fn clear<'a, E>(&self, ex: E, source: Source) -> Result<u64, Error>
+    where
+        E: Executor<'a, Database = Postgres>,
+    {
+        sqlx::query_scalar::<_, i64>(
+            r#"
+            WITH removed AS (
+                DELETE FROM xp WHERE guild_id = $1
+                RETURNING guild_id, user_id, score
+            ),
+            ledger AS (
+                INSERT INTO xp_ledger (guild_id, user_id, amount, source)
+                SELECT guild_id, user_id, -score, $2 FROM removed WHERE score <> 0
+            )
+            SELECT COUNT(*) FROM removed
+            "#,
+        )
+        .bind(self.0)
+        .bind(source.name())
+        .fetch_one(ex)
+        .await
+        .map(|removed| removed as u64)
+    }
+}
+
+/// Saves many awards at once.
+///
+/// The first message of each award gets one experience for every second since
+/// the user was last awarded, up to `max`. Users that were never awarded get
+/// `max`. The cooldown is kept in the database, so it survives restarts and is
+/// shared between instances. If another instance awarded a user while this
+/// was running, that user's award is dropped and left out of the result.
+///
+/// Everything awarded is recorded in the ledger as [`Source::Message`], which
+/// is also used to keep users under their guild's
+/// [hourly cap](super::filter::Filters::hourly_cap).
+pub async fn award<'a, E>(ex: E, awards: &[Award], max: i32) -> Result<Vec<Awarded>, Error>
+where
+    E: Executor<'a, Database = Postgres>,
+{
+    let mut guild_ids = Vec::with_capacity(awards.len());
+    let mut user_ids = Vec::with_capacity(awards.len());
+    let mut first_ats = Vec::with_capacity(awards.len());
+    let mut last_ats = Vec::with_capacity(awards.len());
+    let mut scores = Vec::with_capacity(awards.len());
+    let mut multipliers = Vec::with_capacity(awards.len());
 
-        Ok(())
+    for award in awards {
+        guild_ids.push(award.guild_id.0 as i64);
+        user_ids.push(award.user_id.0 as i64);
+        first_ats.push(epoch(award.first_at));
+        last_ats.push(epoch(award.last_at));
+        scores.push(award.score);
+        multipliers.push(award.multiplier);
     }
+
+    sqlx::query_as(
+        r#"
+        WITH awards AS (
+            SELECT
+                a.guild_id,
+                a.user_id,
+                -- LEAST ignores the cap if it is NULL
+                GREATEST(LEAST(
+                    a.score + FLOOR(a.multiplier * GREATEST(LEAST(COALESCE(
+                        FLOOR(a.first_at - EXTRACT(EPOCH FROM xp.last_awarded_at)::DOUBLE PRECISION)::INTEGER,
+                        $6
+                    ), $6), 0))::INTEGER,
+                    f.hourly_cap - (
+                        SELECT COALESCE(SUM(l.amount), 0)::INTEGER FROM xp_ledger l
+                        WHERE l.guild_id = a.guild_id
+                            AND l.user_id = a.user_id
+                            AND l.source = 'message'
+                            AND l.created_at > to_timestamp(a.last_at) - INTERVAL '1 hour'
+                    )
+                ), 0) AS awarded,
+                to_timestamp(a.last_at) AS last_awarded_at,
+                xp.last_awarded_at AS previous_at
+            FROM UNNEST(
+                $1::BIGINT[],
+                $2::BIGINT[],
+                $3::DOUBLE PRECISION[],
+                $4::DOUBLE PRECISION[],
+                $5::INTEGER[],
+                $7::DOUBLE PRECISION[]
+            ) AS a(guild_id, user_id, first_at, last_at, score, multiplier)
+            LEFT JOIN xp ON xp.guild_id = a.guild_id AND xp.user_id = a.user_id
+            LEFT JOIN xp_filters f ON f.guild_id = a.guild_id
+        ),
+        upserted AS (
+            INSERT INTO xp (guild_id, user_id, score, last_awarded_at)
+            SELECT guild_id, user_id, awarded, last_awarded_at FROM awards
+            ON CONFLICT (user_id, guild_id) DO UPDATE
+            SET
+                score = xp.score + EXCLUDED.score,
+                last_awarded_at = EXCLUDED.last_awarded_at
+            -- the cooldown is checked against the locked row, so if another
+            -- flush awarded the user since, this award is dropped instead of
+            -- being given twice
+            WHERE xp.last_awarded_at IS NOT DISTINCT FROM (
+                SELECT previous_at FROM awards
+                WHERE awards.guild_id = xp.guild_id AND awards.user_id = xp.user_id
+            )
+            RETURNING guild_id, user_id, score
+        ),
+        ledger AS (
+            INSERT INTO xp_ledger (guild_id, user_id, amount, source, created_at)
+            SELECT guild_id, user_id, awarded, 'message', last_awarded_at
+            FROM awards JOIN upserted USING (guild_id, user_id)
+            WHERE awarded <> 0
+        )
+        SELECT upserted.guild_id, upserted.user_id, upserted.score, awards.awarded
+        FROM upserted JOIN awards USING (guild_id, user_id)
+        "#,
+    )
+    .bind(guild_ids)
+    .bind(user_ids)
+    .bind(first_ats)
+    .bind(last_ats)
+    .bind(scores)
+    .bind(max)
+    .bind(multipliers)
+    .fetch_all(ex)
+    .await
 }
 
-/// Calculates the level for a given score.
-pub fn level(score: i32) -> i32 {
-    (score as f64 / 30.).sqrt() as i32 + 1
+fn epoch(time: SystemTime) -> f64 {
+    time.duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs_f64())
+        .unwrap_or(0.)
 }
@@ -3,14 +3,17 @@
 
 mod cons;
 pub mod context;
+pub mod router;
 
 pub use anyhow::Error;
 pub use cons::Cons;
 pub use context::Context;
+pub use router::{Command, Component, Router};
 pub use twilight_model::gateway::event::Event;
 
 use std::future::Future;
 
+use tokio::sync::mpsc;
 use tokio_stream::{Stream, StreamExt};
 
 /// A service type.
@@ -50,6 +53,20 @@ impl Services<()> {
             cx: self.cx,
         }
     }
+
+    /// Add an already constructed service to the service collection.
+    ///
+    /// This is useful for services that need to be configured first, like a
+    /// [`Router`].
+    pub fn with<S>(self, service: S) -> Services<S>
+    where
+        S: for<'a> Service<'a> + Send + Sync + Clone + 'static,
+    {
+        Services {
+            service,
+            cx: self.cx,
+        }
+    }
 }
 
 impl<T> Services<T>
@@ -67,12 +84,45 @@ where
         }
     }
 
-    /// Runs the services for each event in the stream.
-    pub async fn run<E>(&self, mut stream: E)
+    /// Add an already constructed service to the service collection.
+    ///
+    /// This is useful for services that need to be configured first, like a
+    /// [`Router`].
+    pub fn with<S>(self, service: S) -> Services<Cons<T, S>>
+    where
+        S: for<'a> Service<'a> + Send + Sync + Clone + 'static,
+    {
+        Services {
+            service: Cons::new(self.service, service),
+            cx: self.cx,
+        }
+    }
+
+    /// Runs the services for each event in the stream, until the stream ends
+    /// or `stop` completes.
+    ///
+    /// Before returning, this waits for the events that are still being
+    /// handled.
+    pub async fn run<E, F>(&self, mut stream: E, stop: F)
     where
         E: Stream<Item = (u64, Event)> + Unpin,
+        F: Future<Output = ()>,
     {
-        while let Some((shard_id, ev)) = stream.next().await {
+        tokio::pin!(stop);
+
+        // every handler holds a sender, so the channel closes once they are
+        // all done
+        let (running, mut done) = mpsc::channel::<()>(1);
+
+        loop {
+            let (shard_id, ev) = tokio::select! {
+                ev = stream.next() => match ev {
+                    Some(ev) => ev,
+                    None => break,
+                },
+                _ = &mut stop => break,
+            };
+
             // print status info
             match ev {
                 Event::ShardConnected(_) => {
@@ -89,11 +139,16 @@ where
 
             let cx = self.cx.clone();
             let service = self.service.clone();
+            let running = running.clone();
 
             tokio::spawn(async move {
                 service.handle(&cx, &ev).await;
+                drop(running);
             });
         }
+
+        drop(running);
+        done.recv().await;
     }
 }
 
@@ -119,10 +174,10 @@ macro_rules! impl_service {
             }
         }
 
-        impl<'f> Service<'f> for $ty {
+        impl<'f> $crate::service::Service<'f> for $ty {
             type Future = impl ::std::future::Future<Output = ()> + 'f;
 
-            fn handle(&'f self, cx: &'f crate::service::Context, ev: &'f crate::service::Event) -> Self::Future {
+            fn handle(&'f self, cx: &'f $crate::service::Context, ev: &'f $crate::service::Event) -> Self::Future {
                 async move {
                     let res = Self::__handle(self, cx, ev).await;
 
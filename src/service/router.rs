@@ -0,0 +1,267 @@
+//! Interaction routing.
+//!
+//! Instead of every command service inspecting every interaction that comes
+//! through the gateway, a [`Router`] looks at the name of the command (or the
+//! `custom_id` of a component) and only dispatches to the handler that was
+//! registered for it.
+
+use super::{Context, Error, Event};
+
+use crate::command::{chat, component, spec::CommandSpec, Response};
+use crate::impl_service;
+
+use twilight_model::application::interaction::Interaction;
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+
+/// A chat command handler.
+///
+/// Errors returned by the handler are logged by the [`Router`].
+pub trait Command<'f> {
+    type Future: Future<Output = Result<(), Error>> + Send + 'f;
+
+    /// Handles a chat command.
+    fn command(&'f self, cx: &'f Context, args: chat::Arguments<'f>) -> Self::Future;
+}
+
+/// A message component handler.
+///
+/// Errors returned by the handler are logged by the [`Router`].
+pub trait Component<'f> {
+    type Future: Future<Output = Result<(), Error>> + Send + 'f;
+
+    /// Handles a message component interaction.
+    fn component(&'f self, cx: &'f Context, args: component::Arguments<'f>) -> Self::Future;
+}
+
+type BoxFuture<'f> = Pin<Box<dyn Future<Output = Result<(), Error>> + Send + 'f>>;
+
+/// Object-safe version of [`Command`].
+pub(crate) trait CommandHandler: Send + Sync {
+    fn call<'f>(&'f self, cx: &'f Context, args: chat::Arguments<'f>) -> BoxFuture<'f>;
+}
+
+impl<T> CommandHandler for T
+where
+    T: for<'f> Command<'f> + Send + Sync,
+{
+    fn call<'f>(&'f self, cx: &'f Context, args: chat::Arguments<'f>) -> BoxFuture<'f> {
+        Box::pin(self.command(cx, args))
+    }
+}
+
+/// Object-safe version of [`Component`].
+trait ComponentHandler: Send + Sync {
+    fn call<'f>(&'f self, cx: &'f Context, args: component::Arguments<'f>) -> BoxFuture<'f>;
+}
+
+impl<T> ComponentHandler for T
+where
+    T: for<'f> Component<'f> + Send + Sync,
+{
+    fn call<'f>(&'f self, cx: &'f Context, args: component::Arguments<'f>) -> BoxFuture<'f> {
+        Box::pin(self.component(cx, args))
+    }
+}
+
+#[derive(Clone, Default)]
+struct Routes {
+    commands: HashMap<String, Arc<dyn CommandHandler>>,
+    components: Vec<(String, Arc<dyn ComponentHandler>)>,
+}
+
+/// Dispatches interactions to the handlers registered for them.
+///
+/// Commands are matched by their full path, which is the command name
+/// followed by any subcommand names, seperated by spaces (`"reactionroles
+/// add"`). Components are matched by the prefix of their `custom_id`, up to
+/// the first `:`.
+///
+/// If nothing matches, the user gets an ephemeral "unknown command" reply.
+///
+/// This type is cheap to clone.
+#[derive(Clone, Default)]
+pub struct Router(Arc<Routes>);
+
+impl Router {
+    /// Creates a new, empty `Router`.
+    pub fn new() -> Router {
+        Router::default()
+    }
+
+    /// Registers the handlers of every command in `specs`.
+    ///
+    /// # Panics
+    /// Panics if a handler was already registered for a command path.
+    pub fn commands(mut self, specs: &[CommandSpec]) -> Router {
+        let routes = Arc::make_mut(&mut self.0);
+
+        for (path, handler) in specs.iter().flat_map(CommandSpec::handlers) {
+            let old = routes.commands.insert(path.clone(), handler.clone());
+
+            assert!(old.is_none(), "command `{}` registered twice", path);
+        }
+
+        self
+    }
+
+    /// Registers a handler for a component `custom_id` prefix.
+    ///
+    /// # Panics
+    /// Panics if a handler was already registered for the prefix.
+    pub fn component<T>(mut self, prefix: &str, handler: T) -> Router
+    where
+        T: for<'f> Component<'f> + Send + Sync + 'static,
+    {
+        let routes = Arc::make_mut(&mut self.0);
+
+        assert!(
+            routes.components.iter().all(|(p, _)| p != prefix),
+            "component prefix `{}` registered twice",
+            prefix,
+        );
+
+        routes
+            .components
+            .push((String::from(prefix), Arc::new(handler)));
+
+        self
+    }
+
+    async fn dispatch_command(&self, cx: &Context, args: chat::Arguments<'_>) -> Result<(), Error> {
+        // walk down the subcommands to find the full path
+        let mut path = String::from(args.name());
+        let mut args = args;
+
+        while let Some((name, sub)) = args.subcommand() {
+            path.push(' ');
+            path.push_str(name);
+            args = sub;
+        }
+
+        match self.0.commands.get(&path) {
+            Some(handler) => handler.call(cx, args).await,
+            None => {
+                warn!("unknown command /{}", path);
+
+                unknown(args.respond()).exec(cx.http()).await
+            }
+        }
+    }
+
+    async fn dispatch_component(
+        &self,
+        cx: &Context,
+        args: component::Arguments<'_>,
+    ) -> Result<(), Error> {
+        let prefix = args.prefix();
+
+        let handler = self
+            .0
+            .components
+            .iter()
+            .find(|(p, _)| p == prefix)
+            .map(|(_, handler)| handler);
+
+        match handler {
+            Some(handler) => handler.call(cx, args).await,
+            None => {
+                warn!("unknown component {}", args.custom_id());
+
+                unknown(args.respond()).exec(cx.http()).await
+            }
+        }
+    }
+}
+
+fn unknown(response: Response) -> Response {
+    response
+        .content("unknown command! it may have been removed or not set up yet.")
+        .ephemeral()
+}
+
+impl_service! {
+    impl Service for Router {
+        async fn handle(&self, cx: &Context, ev: &Event) -> Result<(), Error> {
+            match ev {
+                Event::InteractionCreate(int) => match &int.0 {
+                    Interaction::ApplicationCommand(cmd) => {
+                        self.dispatch_command(cx, chat::Arguments::new(cmd)).await
+                    }
+                    Interaction::MessageComponent(cmp) => {
+                        self.dispatch_component(cx, component::Arguments::new(cmp)).await
+                    }
+                    _ => Ok(()),
+                },
+                _ => Ok(()),
+            }
+        }
+    }
+}
+
+/// Macro for easily implementing a [`Command`].
+///
+/// This requires Nightly rust and `#![feature(type_alias_impl_trait)]` to be
+/// enabled.
+#[macro_export]
+macro_rules! impl_command {
+    {
+        impl Command for $ty:path {
+            async fn command(&$self_ident:ident, $cx_ident:ident: $cx_ty:ty, $args_ident:ident: $args_ty:ty $(,)?) -> Result<(), $err_ty:path>
+            $body:tt
+        }
+    } => {
+        impl $ty {
+            async fn __command(
+                $self_ident: &Self,
+                $cx_ident: $cx_ty,
+                $args_ident: $args_ty,
+            ) -> ::std::result::Result<(), $err_ty> {
+                $body
+            }
+        }
+
+        impl<'f> $crate::service::Command<'f> for $ty {
+            type Future = impl ::std::future::Future<Output = ::std::result::Result<(), $crate::service::Error>> + 'f;
+
+            fn command(&'f self, cx: &'f $crate::service::Context, args: $crate::command::chat::Arguments<'f>) -> Self::Future {
+                async move { Self::__command(self, cx, args).await.map_err(From::from) }
+            }
+        }
+    }
+}
+
+/// Macro for easily implementing a [`Component`].
+///
+/// This requires Nightly rust and `#![feature(type_alias_impl_trait)]` to be
+/// enabled.
+#[macro_export]
+macro_rules! impl_component {
+    {
+        impl Component for $ty:path {
+            async fn component(&$self_ident:ident, $cx_ident:ident: $cx_ty:ty, $args_ident:ident: $args_ty:ty $(,)?) -> Result<(), $err_ty:path>
+            $body:tt
+        }
+    } => {
+        impl $ty {
+            async fn __component(
+                $self_ident: &Self,
+                $cx_ident: $cx_ty,
+                $args_ident: $args_ty,
+            ) -> ::std::result::Result<(), $err_ty> {
+                $body
+            }
+        }
+
+        impl<'f> $crate::service::Component<'f> for $ty {
+            type Future = impl ::std::future::Future<Output = ::std::result::Result<(), $crate::service::Error>> + 'f;
+
+            fn component(&'f self, cx: &'f $crate::service::Context, args: $crate::command::component::Arguments<'f>) -> Self::Future {
+                async move { Self::__component(self, cx, args).await.map_err(From::from) }
+            }
+        }
+    }
+}